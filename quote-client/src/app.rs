@@ -1,93 +1,380 @@
+mod alerts;
 mod cancellation_token;
+mod commands;
+mod console;
+mod dashboard;
 mod error;
-mod ping;
-mod read_udp_response;
-mod server_connect;
+mod fail_back;
+mod live_subscription;
+mod output;
+mod quote_format;
+mod reconnect;
+mod recording;
+mod server;
 
 use crate::app::cancellation_token::CancellationToken;
-use crate::app::read_udp_response::read_udp_response;
-use crate::app::server_connect::connect;
-use std::net::{IpAddr, UdpSocket};
-use std::sync::Arc;
-use std::time::Duration;
+use crate::app::commands::start_command_console;
+use crate::app::dashboard::{DashboardFeed, Exit, run_dashboard};
+use crate::app::fail_back::start_fail_back_probe;
+use crate::app::live_subscription::LiveSubscription;
+use crate::app::output::{Output, Status};
+use crate::app::quote_format::QuoteKind;
+use crate::app::reconnect::{Reconnect, wait};
+use crate::app::recording::{Record, RecordReader, RecordWriter};
+use chrono::Utc;
+use quote_client::{
+    QuoteSubscriber, ServerConnectError, Subscription, Transport, history, list_groups, snapshot,
+};
+use quote_streaming::SubscriptionOptions;
 
-const DEFAULT_SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+use crate::app::alerts::Alerts;
+pub(super) use crate::app::console::Console;
+pub(super) use crate::app::quote_format::{QuoteFormat, Template};
+pub(super) use crate::app::recording::Encoding;
+pub(super) use crate::app::server::Server;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How far back `history` looks without `--from`, in seconds.
+const DEFAULT_HISTORY_SPAN: i64 = 60 * 60;
 
 pub(super) struct App {
     cancellation_token: Arc<CancellationToken>,
-    server_address: IpAddr,
-    server_port: u16,
-    tickers: Vec<String>,
-    client_address: IpAddr,
-    client_port: u16,
+    servers: Vec<Server>,
+    /// The subscription, changed by the command console while streaming.
+    live: Arc<LiveSubscription>,
+    udp_port: Option<u16>,
+    transport: Transport,
+    options: SubscriptionOptions,
+    /// How often to probe the preferred servers while streaming from a backup one.
+    fail_back_interval: Option<Duration>,
+    /// Where the received events are recorded, across reconnects.
+    recording: Option<RecordWriter>,
+    /// The rules the received quotes are checked against.
+    alerts: Option<Alerts>,
 }
 
 impl App {
     pub(super) fn new(
-        server_address: IpAddr,
-        server_port: u16,
-        tickers: Vec<String>,
-        client_address: IpAddr,
-        client_port: u16,
+        servers: Vec<Server>,
+        subscription: Subscription,
+        udp_port: Option<u16>,
+        transport: Transport,
+        options: SubscriptionOptions,
+        fail_back_interval: Option<Duration>,
     ) -> Self {
         Self {
             cancellation_token: Arc::new(CancellationToken::default()),
-            server_address,
-            server_port,
-            tickers,
-            client_address,
-            client_port,
+            servers,
+            live: Arc::new(LiveSubscription::new(subscription)),
+            udp_port,
+            transport,
+            options,
+            fail_back_interval,
+            recording: None,
+            alerts: None,
         }
     }
 
-    pub(super) fn run(self) -> Result<(), error::AppError> {
+    /// Records the received events to a file besides showing them.
+    pub(super) fn with_recording(
+        mut self,
+        path: &Path,
+        encoding: Encoding,
+    ) -> Result<Self, error::AppError> {
+        self.recording = Some(RecordWriter::create(path, encoding)?);
+        Ok(self)
+    }
+
+    pub(super) fn with_alerts(mut self, alerts: Alerts) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Reads commands changing the subscription from stdin while the app runs.
+    pub(super) fn start_command_console(&self) {
+        start_command_console(Arc::clone(&self.live), Arc::clone(&self.cancellation_token));
+    }
+
+    pub(super) fn run(self, output: &mut dyn Output) -> Result<(), error::AppError> {
+        error::closed_output_ends(self.run_sessions(output))
+    }
+
+    fn run_sessions(mut self, output: &mut dyn Output) -> Result<(), error::AppError> {
+        if self.servers.is_empty() {
+            return Err(error::AppError::NoServers);
+        }
         set_ctrlc_handler(Arc::clone(&self.cancellation_token))?;
-        self.show_app_title();
-        let socket = self.create_udp_socket()?;
-        connect(
-            self.tickers,
-            self.server_address,
-            self.server_port,
-            self.client_address,
-            self.client_port,
-        )?;
-        let ping_thread = ping::start_ping(
-            Arc::clone(&self.cancellation_token),
-            Arc::clone(&socket),
-            self.client_address,
-            self.server_port,
-        );
-        read_udp_response(Arc::clone(&self.cancellation_token), socket)?;
-        ping_thread
+
+        let mut reconnect = Reconnect::new(self.servers.clone());
+        loop {
+            let server = reconnect.server().clone();
+            // Ends this subscription only, the app may subscribe again
+            let session_token = Arc::new(CancellationToken::child(&self.cancellation_token));
+            let fail_back_probe = self
+                .fail_back_interval
+                .filter(|_| !reconnect.preferred().is_empty())
+                .map(|interval| {
+                    start_fail_back_probe(
+                        Arc::clone(&session_token),
+                        reconnect.preferred().to_vec(),
+                        interval,
+                    )
+                });
+
+            output
+                .status(Status::Connecting(server.clone()))
+                .map_err(error::AppError::Output)?;
+            let result = self.stream(&server, &session_token, &mut reconnect, output);
+            self.live.disconnected();
+            session_token.cancel();
+            let fail_back = match fail_back_probe {
+                Some(probe) => probe
+                    .join()
+                    .map_err(|_| error::AppError::JoinThread("Fail-back probe".to_string()))?,
+                None => None,
+            };
+
+            if self.cancellation_token.is_cancelled() {
+                return Ok(());
+            }
+            if let Some(index) = fail_back {
+                reconnect.fail_back(index);
+                output
+                    .status(Status::FailingBack(reconnect.server().clone()))
+                    .map_err(error::AppError::Output)?;
+                continue;
+            }
+            match result {
+                Err(e) if e.is_disconnection() => output
+                    .status(Status::Disconnected {
+                        server: server.clone(),
+                        error: e.to_string(),
+                    })
+                    .map_err(error::AppError::Output)?,
+                result => return result,
+            }
+
+            reconnect.disconnected();
+            if reconnect.fail_over() {
+                let delay = reconnect.next_delay();
+                output
+                    .status(Status::Reconnecting(delay))
+                    .map_err(error::AppError::Output)?;
+                if !wait(delay, &self.cancellation_token) {
+                    return Ok(());
+                }
+            }
+            if reconnect.server() != &server {
+                output
+                    .status(Status::FailingOver(reconnect.server().clone()))
+                    .map_err(error::AppError::Output)?;
+            }
+        }
+    }
+
+    /// Streams on a thread of its own and shows the quotes in a terminal dashboard.
+    pub(super) fn run_dashboard(self) -> Result<(), error::AppError> {
+        let cancellation_token = Arc::clone(&self.cancellation_token);
+        let (updates_tx, updates_rx) = mpsc::channel();
+        let stream_thread = thread::spawn(move || self.run(&mut DashboardFeed::new(updates_tx)));
+
+        let exit = run_dashboard(updates_rx, &cancellation_token);
+        cancellation_token.cancel();
+        // A thread still waiting for the server would hold up quitting for the read timeout
+        if exit.map_err(error::AppError::Terminal)? == Exit::Quit {
+            return Ok(());
+        }
+        stream_thread
             .join()
-            .map_err(|_| error::AppError::JoinThread("Ping thread".to_string()))?;
+            .map_err(|_| error::AppError::JoinThread("Stream thread".to_string()))?
+    }
+
+    fn stream(
+        &mut self,
+        server: &Server,
+        session_token: &CancellationToken,
+        reconnect: &mut Reconnect,
+        output: &mut dyn Output,
+    ) -> Result<(), error::AppError> {
+        let mut subscriber = QuoteSubscriber::new(
+            SocketAddr::new(server.address(), server.port()),
+            self.live.subscription(),
+        )
+        .with_options(self.options.clone())
+        .with_transport(self.transport);
+        if let Some(port) = self.udp_port {
+            subscriber = subscriber.with_udp_port(port);
+        }
+
+        let mut stream = subscriber.subscribe()?;
+        self.live.subscribed(stream.controller(), server.clone())?;
+        output
+            .status(Status::Subscribed {
+                server: server.clone(),
+                outage: reconnect.subscribed(),
+            })
+            .map_err(error::AppError::Output)?;
+        if !stream.multicast_groups().is_empty() {
+            output
+                .status(Status::MulticastGroups(stream.multicast_groups().to_vec()))
+                .map_err(error::AppError::Output)?;
+        }
+        while !session_token.is_cancelled() {
+            let event = stream.next_event()?;
+            if let Some(recording) = self.recording.as_mut()
+                && let Some(record) = Record::new(Utc::now(), stream.sequence(), event.clone())
+            {
+                recording.write(&record)?;
+            }
+            if let Some(alerts) = self.alerts.as_mut() {
+                alerts.check(&event);
+            }
+            self.live.received(&event);
+            output.event(event).map_err(error::AppError::Output)?;
+        }
         Ok(())
     }
 
-    fn show_app_title(&self) {
+    pub(super) fn show_app_title(&self) {
         println!("Client started");
         println!("======================");
-        println!(
-            "Server address  {}:{}",
-            self.server_address, self.server_port
-        );
-        println!("Tickers         {}", self.tickers.join(", "));
+        for server in &self.servers {
+            println!(
+                "Server address  {} (priority {})",
+                server,
+                server.priority()
+            );
+        }
+        let subscription = self.live.subscription();
+        if !subscription.tickers().is_empty() {
+            println!("Tickers         {}", subscription.tickers().join(", "));
+        }
+        if !subscription.groups().is_empty() {
+            println!("Groups          {}", subscription.groups().join(", "));
+        }
+        println!("Commands        add, remove, pause, resume, stats, quit, help");
         println!("======================");
     }
+}
+
+pub(super) fn show_groups(servers: &[Server]) -> Result<(), error::AppError> {
+    for group in first_answer(servers, list_groups)? {
+        println!("{:<15} {}", group.name(), group.tickers().join(", "));
+    }
+    Ok(())
+}
+
+pub(super) fn show_snapshot(
+    servers: &[Server],
+    tickers: Vec<String>,
+    format: QuoteFormat,
+) -> Result<(), error::AppError> {
+    let quotes = first_answer(servers, |address, port| {
+        snapshot(tickers.clone(), address, port)
+    })?;
+    let mut console = Console::new(format);
+    let result = quotes
+        .iter()
+        .try_for_each(|quote| console.quote(quote, QuoteKind::Quote));
+    error::closed_output_ends(result.map_err(error::AppError::Output))
+}
+
+pub(super) fn show_history(
+    servers: &[Server],
+    ticker: String,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: u32,
+    format: QuoteFormat,
+) -> Result<(), error::AppError> {
+    let to = to.unwrap_or_else(|| Utc::now().timestamp());
+    let from = from.unwrap_or(to - DEFAULT_HISTORY_SPAN);
+    let quotes = first_answer(servers, |address, port| {
+        history(ticker.clone(), from, to, limit, address, port)
+    })?;
+    let mut console = Console::new(format);
+    let result = quotes
+        .iter()
+        .try_for_each(|quote| console.quote(quote, QuoteKind::Quote));
+    error::closed_output_ends(result.map_err(error::AppError::Output))
+}
+
+/// Prints the events of a recording, spaced as they were received and sped up by `speed`,
+/// or as fast as possible without one, checking them against the alert rules.
+pub(super) fn replay(
+    path: &Path,
+    speed: Option<f64>,
+    format: QuoteFormat,
+    alerts: Option<Alerts>,
+) -> Result<(), error::AppError> {
+    error::closed_output_ends(replay_records(path, speed, format, alerts))
+}
+
+fn replay_records(
+    path: &Path,
+    speed: Option<f64>,
+    format: QuoteFormat,
+    mut alerts: Option<Alerts>,
+) -> Result<(), error::AppError> {
+    let mut console = Console::new(format);
+    let start = Instant::now();
+    let mut first_received = None;
+    for record in RecordReader::open(path)? {
+        let record = record?;
+        if let Some(speed) = speed {
+            let first_received = *first_received.get_or_insert(record.received());
+            let offset = u64::try_from(record.received() - first_received).unwrap_or_default();
+            let due = start + Duration::from_micros(offset).div_f64(speed);
+            thread::sleep(due.saturating_duration_since(Instant::now()));
+        }
+        let event = record.into_event();
+        if let Some(alerts) = alerts.as_mut() {
+            alerts.check(&event);
+        }
+        console.event(event).map_err(error::AppError::Output)?;
+    }
+    Ok(())
+}
 
-    fn create_udp_socket(&self) -> Result<Arc<UdpSocket>, error::AppError> {
-        let socket = Arc::new(
-            UdpSocket::bind((self.client_address, self.client_port))
-                .map_err(error::AppError::UdpSocket)?,
-        );
-        socket
-            .set_read_timeout(Some(DEFAULT_SOCKET_TIMEOUT))
-            .map_err(error::AppError::UdpSocket)?;
-        socket
-            .set_write_timeout(Some(DEFAULT_SOCKET_TIMEOUT))
-            .map_err(error::AppError::UdpSocket)?;
-        Ok(socket)
+/// Reads the alert rules and where the alerts are delivered to.
+pub(super) fn load_alerts(
+    rules: &Path,
+    file: Option<&Path>,
+    command: Option<String>,
+) -> Result<Alerts, error::AppError> {
+    let mut alerts = Alerts::load(rules)?;
+    if let Some(file) = file {
+        alerts = alerts.with_file(file)?;
+    }
+    if let Some(command) = command {
+        alerts = alerts.with_command(command);
+    }
+    Ok(alerts)
+}
+
+/// Sends the request to the servers in order of priority until one of them answers.
+fn first_answer<T>(
+    servers: &[Server],
+    request: impl Fn(IpAddr, u16) -> Result<T, ServerConnectError>,
+) -> Result<T, error::AppError> {
+    let mut servers = servers.to_vec();
+    servers.sort_by_key(Server::priority);
+
+    let mut result = Err(error::AppError::NoServers);
+    for server in servers {
+        result = request(server.address(), server.port()).map_err(error::AppError::from);
+        match &result {
+            Err(error::AppError::ServerConnect(ServerConnectError::Connection(e))) => {
+                eprintln!("{}: {}", server, e)
+            }
+            _ => break,
+        }
     }
+    result
 }
 
 fn set_ctrlc_handler(cancellation_token: Arc<CancellationToken>) -> Result<(), ctrlc::Error> {
@@ -0,0 +1,127 @@
+mod rules;
+
+use crate::app::alerts::rules::{Alert, RuleState, parse_rule};
+use crate::app::quote_format::{price, timestamp};
+use quote_client::Event;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum AlertError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid rule on line {line}: {message}")]
+    InvalidRule { line: usize, message: String },
+}
+
+/// Checks the received quotes against the rules of an alert file and reports the rules that
+/// fire: to stderr, or to a file and a command when any of them is set.
+pub(crate) struct Alerts {
+    rules: Vec<RuleState>,
+    file: Option<File>,
+    /// A shell command run for each alert, with the alert in its environment.
+    command: Option<String>,
+}
+
+impl Alerts {
+    /// Reads the rules, one `TICKER CONDITION THRESHOLD [COOLDOWN]` per line.
+    ///
+    /// Empty lines and lines starting with `#` are skipped.
+    pub(crate) fn load(path: &Path) -> Result<Self, AlertError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut rules = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule = parse_rule(line).map_err(|message| AlertError::InvalidRule {
+                line: index + 1,
+                message,
+            })?;
+            rules.push(RuleState::new(rule));
+        }
+        Ok(Self {
+            rules,
+            file: None,
+            command: None,
+        })
+    }
+
+    /// Appends the alerts to a file, created if it does not exist.
+    pub(crate) fn with_file(mut self, path: &Path) -> Result<Self, AlertError> {
+        self.file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        Ok(self)
+    }
+
+    pub(crate) fn with_command(mut self, command: String) -> Self {
+        self.command = Some(command);
+        self
+    }
+
+    /// Checks new quotes and snapshots. Retransmitted quotes are older than the prices the
+    /// rules have already seen, so they are left out.
+    pub(crate) fn check(&mut self, event: &Event) {
+        let (Event::Quote(quote) | Event::Snapshot(quote)) = event else {
+            return;
+        };
+        let alerts: Vec<Alert> = self
+            .rules
+            .iter_mut()
+            .filter_map(|rule| rule.check(quote))
+            .collect();
+        for alert in alerts {
+            self.emit(&alert);
+        }
+    }
+
+    /// Failing to deliver an alert is reported, but does not stop the stream.
+    fn emit(&mut self, alert: &Alert) {
+        let line = format!("{} ALERT {}", timestamp(&alert.quote), alert.message);
+        if self.file.is_none() && self.command.is_none() {
+            eprintln!("{}", line);
+        }
+        if let Some(file) = self.file.as_mut()
+            && let Err(e) = writeln!(file, "{}", line)
+        {
+            eprintln!("Failed to write alert: {}", e);
+        }
+        if let Some(command) = self.command.as_ref() {
+            run_command(command, alert);
+        }
+    }
+}
+
+/// Runs the command through the shell without waiting for it.
+fn run_command(command: &str, alert: &Alert) {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let child = shell
+        .arg(command)
+        .env("QUOTE_ALERT_TICKER", alert.quote.ticker())
+        .env("QUOTE_ALERT_PRICE", price(&alert.quote).to_string())
+        .env("QUOTE_ALERT_TIMESTAMP", timestamp(&alert.quote))
+        .env("QUOTE_ALERT_MESSAGE", &alert.message)
+        .stdin(Stdio::null())
+        // Output of the command would land in the middle of the printed quotes
+        .stdout(Stdio::null())
+        .spawn();
+    match child {
+        // Reaped on a thread of its own, so a slow command does not hold up the quotes
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => eprintln!("Failed to run alert command: {}", e),
+    }
+}
@@ -0,0 +1,294 @@
+use quote_streaming::StockQuote;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+
+/// How long a rule stays quiet after it fired, unless the rule says otherwise.
+const DEFAULT_COOLDOWN: i64 = 60;
+
+/// When a rule fires.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Condition {
+    /// The price rises from below the level, in cents, to the level or above.
+    Above(i64),
+    /// The price falls from above the level, in cents, to the level or below.
+    Below(i64),
+    /// The price crosses the level, in cents, in either direction.
+    Crosses(i64),
+    /// The price moves by at least this percentage within the window, in seconds.
+    Move { percent: Decimal, window: i64 },
+}
+
+/// One line of a rules file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Rule {
+    ticker: String,
+    condition: Condition,
+    /// Seconds to stay quiet after firing.
+    cooldown: i64,
+}
+
+/// Parses a `TICKER CONDITION THRESHOLD [COOLDOWN]` line, e.g. `AAPL above 200 5m` or
+/// `NVDA move 3%/10m`.
+pub(crate) fn parse_rule(line: &str) -> Result<Rule, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [ticker, condition, threshold, cooldown @ ..] = fields.as_slice() else {
+        return Err(format!(
+            "expected `TICKER CONDITION THRESHOLD [COOLDOWN]`, got `{}`",
+            line
+        ));
+    };
+    let condition = match condition.to_lowercase().as_str() {
+        "above" => Condition::Above(parse_price(threshold)?),
+        "below" => Condition::Below(parse_price(threshold)?),
+        "crosses" => Condition::Crosses(parse_price(threshold)?),
+        "move" => {
+            let (percent, window) = threshold
+                .split_once('/')
+                .ok_or_else(|| format!("expected `PERCENT%/WINDOW`, got `{}`", threshold))?;
+            let percent = percent.strip_suffix('%').unwrap_or(percent);
+            let percent: Decimal = percent
+                .parse()
+                .map_err(|e| format!("invalid percentage `{}`: {}", percent, e))?;
+            if percent <= Decimal::ZERO {
+                return Err(format!("percentage must be positive, got `{}`", percent));
+            }
+            Condition::Move {
+                percent,
+                window: parse_duration(window)?,
+            }
+        }
+        condition => {
+            return Err(format!(
+                "unknown condition `{}`, expected above, below, crosses or move",
+                condition
+            ));
+        }
+    };
+    let cooldown = match cooldown {
+        [] => DEFAULT_COOLDOWN,
+        [cooldown] => parse_duration(cooldown)?,
+        [_, extra, ..] => return Err(format!("unexpected `{}` after the cooldown", extra)),
+    };
+
+    Ok(Rule {
+        ticker: ticker.to_string(),
+        condition,
+        cooldown,
+    })
+}
+
+/// A rule that fired.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Alert {
+    pub(crate) quote: StockQuote,
+    pub(crate) message: String,
+}
+
+/// A rule with the prices it has seen.
+pub(crate) struct RuleState {
+    rule: Rule,
+    previous: Option<i64>,
+    /// Timestamps and prices of a move rule's window with no lower price after them, rising
+    /// from the lowest price at the front.
+    lows: VecDeque<(i64, i64)>,
+    /// Timestamps and prices of a move rule's window with no higher price after them, falling
+    /// from the highest price at the front.
+    highs: VecDeque<(i64, i64)>,
+    last_fired: Option<i64>,
+}
+
+impl RuleState {
+    pub(crate) fn new(rule: Rule) -> Self {
+        Self {
+            rule,
+            previous: None,
+            lows: VecDeque::new(),
+            highs: VecDeque::new(),
+            last_fired: None,
+        }
+    }
+
+    /// Returns an alert when the quote fulfils the rule and the rule is not cooling down.
+    ///
+    /// Time is taken from the quote timestamps, so a replayed stream fires like the live one.
+    pub(crate) fn check(&mut self, quote: &StockQuote) -> Option<Alert> {
+        if quote.ticker() != self.rule.ticker {
+            return None;
+        }
+        let (price, now) = (quote.price(), quote.timestamp());
+        let previous = self.previous.replace(price);
+        let message = match self.rule.condition {
+            Condition::Above(level) => previous
+                .filter(|previous| *previous < level && price >= level)
+                .map(|_| format!("crossed above {}", cents(level))),
+            Condition::Below(level) => previous
+                .filter(|previous| *previous > level && price <= level)
+                .map(|_| format!("crossed below {}", cents(level))),
+            Condition::Crosses(level) => previous
+                .filter(|previous| (*previous < level) != (price < level))
+                .map(|_| format!("crossed {}", cents(level))),
+            Condition::Move { percent, window } => {
+                for extremes in [&mut self.lows, &mut self.highs] {
+                    while extremes
+                        .front()
+                        .is_some_and(|(timestamp, _)| now - timestamp > window)
+                    {
+                        extremes.pop_front();
+                    }
+                }
+                while self.lows.back().is_some_and(|(_, low)| *low >= price) {
+                    self.lows.pop_back();
+                }
+                while self.highs.back().is_some_and(|(_, high)| *high <= price) {
+                    self.highs.pop_back();
+                }
+                self.lows.push_back((now, price));
+                self.highs.push_back((now, price));
+                self.moved(price, percent).map(|change| {
+                    format!(
+                        "moved {:+.2}% within {}",
+                        change.round_dp(2),
+                        format_duration(window)
+                    )
+                })
+            }
+        }?;
+
+        if self
+            .last_fired
+            .is_some_and(|fired| now - fired < self.rule.cooldown)
+        {
+            return None;
+        }
+        self.last_fired = Some(now);
+        Some(Alert {
+            quote: quote.clone(),
+            message: format!("{} {} at {}", quote.ticker(), message, cents(price)),
+        })
+    }
+
+    /// The change of the price from the lowest or highest one in the window, when it reaches
+    /// the percentage.
+    fn moved(&self, price: i64, percent: Decimal) -> Option<Decimal> {
+        let (_, low) = *self.lows.front()?;
+        let (_, high) = *self.highs.front()?;
+        [low, high]
+            .into_iter()
+            .filter_map(|base| {
+                (Decimal::from(price - base) * Decimal::ONE_HUNDRED)
+                    .checked_div(Decimal::from(base))
+            })
+            .filter(|change| change.abs() >= percent)
+            .max_by_key(|change| change.abs())
+    }
+}
+
+fn cents(price: i64) -> Decimal {
+    Decimal::new(price, 2)
+}
+
+fn format_duration(seconds: i64) -> String {
+    match seconds {
+        seconds if seconds > 0 && seconds % 3600 == 0 => format!("{}h", seconds / 3600),
+        seconds if seconds > 0 && seconds % 60 == 0 => format!("{}m", seconds / 60),
+        seconds => format!("{}s", seconds),
+    }
+}
+
+fn parse_price(value: &str) -> Result<i64, String> {
+    let mut price: Decimal = value
+        .parse()
+        .map_err(|e| format!("invalid price `{}`: {}", value, e))?;
+    price.rescale(2);
+    i64::try_from(price.mantissa()).map_err(|e| format!("invalid price `{}`: {}", value, e))
+}
+
+/// Parses a number of seconds, minutes or hours such as `30s`, `5m` or `1h`.
+fn parse_duration(value: &str) -> Result<i64, String> {
+    let digits = value.trim_end_matches(char::is_alphabetic);
+    let multiplier = match &value[digits.len()..] {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        unit => {
+            return Err(format!(
+                "unknown unit `{}` in `{}`, expected s, m or h",
+                unit, value
+            ));
+        }
+    };
+    digits
+        .parse::<i64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid duration `{}`", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_fire() {
+        assert_eq!(
+            parse_rule("AAPL above 200 5m").unwrap(),
+            Rule {
+                ticker: "AAPL".to_string(),
+                condition: Condition::Above(20000),
+                cooldown: 300,
+            }
+        );
+        assert!(parse_rule("AAPL above").is_err());
+        assert!(parse_rule("AAPL rises 200").is_err());
+        assert!(parse_rule("NVDA move 3%/5d").is_err());
+
+        let mut above = RuleState::new(parse_rule("AAPL above 200 1m").unwrap());
+        let fired: Vec<bool> = [(19900, 0), (20010, 1), (19950, 2), (20100, 30), (20100, 90)]
+            .into_iter()
+            .map(|(price, time)| {
+                above
+                    .check(&StockQuote::new("AAPL", price, 1, time))
+                    .is_some()
+            })
+            .collect();
+        // The second crossing falls within the cooldown
+        assert_eq!(fired, [false, true, false, false, false]);
+
+        let mut moved = RuleState::new(parse_rule("NVDA move 3%/5m 0s").unwrap());
+        assert_eq!(moved.check(&StockQuote::new("NVDA", 10000, 1, 0)), None);
+        assert_eq!(moved.check(&StockQuote::new("NVDA", 10200, 1, 60)), None);
+        // Outside the window the first price no longer counts
+        assert_eq!(moved.check(&StockQuote::new("NVDA", 10350, 1, 400)), None);
+        let alert = moved.check(&StockQuote::new("NVDA", 9900, 1, 420)).unwrap();
+        assert_eq!(alert.message, "NVDA moved -4.35% within 5m at 99.00");
+
+        // The high of 104.00 left the window, the move is measured from 103.50
+        let mut moved = RuleState::new(parse_rule("MSFT move 5%/10s 0s").unwrap());
+        let fired: Vec<Option<String>> = [
+            (10000, 0),
+            (10400, 5),
+            (10300, 11),
+            (10350, 14),
+            (9800, 16),
+            (10300, 30),
+        ]
+        .into_iter()
+        .map(|(price, time)| {
+            moved
+                .check(&StockQuote::new("MSFT", price, 1, time))
+                .map(|alert| alert.message)
+        })
+        .collect();
+        assert_eq!(
+            fired,
+            [
+                None,
+                None,
+                None,
+                None,
+                Some("MSFT moved -5.31% within 10s at 98.00".to_string()),
+                None,
+            ]
+        );
+    }
+}
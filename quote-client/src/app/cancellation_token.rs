@@ -1,14 +1,31 @@
+use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 
 #[derive(Default)]
-pub(crate) struct CancellationToken(AtomicBool);
+pub(crate) struct CancellationToken {
+    cancelled: AtomicBool,
+    parent: Option<Arc<CancellationToken>>,
+}
 
 impl CancellationToken {
+    /// Creates a token that is also cancelled when the parent is.
+    pub(crate) fn child(parent: &Arc<CancellationToken>) -> Self {
+        Self {
+            cancelled: AtomicBool::new(false),
+            parent: Some(Arc::clone(parent)),
+        }
+    }
+
     pub(crate) fn cancel(&self) {
-        self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+        self.cancelled
+            .store(true, std::sync::atomic::Ordering::SeqCst);
     }
 
     pub(crate) fn is_cancelled(&self) -> bool {
-        self.0.load(std::sync::atomic::Ordering::SeqCst)
+        self.cancelled.load(std::sync::atomic::Ordering::SeqCst)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.is_cancelled())
     }
 }
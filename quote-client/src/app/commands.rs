@@ -0,0 +1,199 @@
+use crate::app::cancellation_token::CancellationToken;
+use crate::app::live_subscription::{Applied, LiveSubscription};
+use quote_streaming::SubscriptionChange;
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+
+const HELP: &str = "\
+Commands:
+  add TICKER...      stream more tickers or glob patterns
+  remove TICKER...   stop streaming tickers or glob patterns
+  pause              stop the quotes, keeping the subscription
+  resume             send quotes again, starting with a snapshot
+  stats              show what was received so far
+  quit               stop the client
+  help               show this help";
+
+/// A line typed on stdin while the client streams.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Command {
+    Add(Vec<String>),
+    Remove(Vec<String>),
+    Pause,
+    Resume,
+    Stats,
+    Quit,
+    Help,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub(crate) enum CommandError {
+    #[error("unknown command `{0}`, type `help` for the commands")]
+    Unknown(String),
+    #[error("`{0}` needs at least one ticker")]
+    MissingTickers(&'static str),
+    #[error("`{0}` takes no arguments")]
+    UnexpectedArguments(&'static str),
+}
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    /// Tickers are separated by spaces or commas.
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty());
+        let name = words.next().unwrap_or_default().to_lowercase();
+        let arguments: Vec<String> = words.map(String::from).collect();
+
+        let (command, name) = match name.as_str() {
+            "add" => return tickers(arguments, "add").map(Command::Add),
+            "remove" => return tickers(arguments, "remove").map(Command::Remove),
+            "pause" => (Command::Pause, "pause"),
+            "resume" => (Command::Resume, "resume"),
+            "stats" => (Command::Stats, "stats"),
+            "quit" | "exit" => (Command::Quit, "quit"),
+            "help" | "?" => (Command::Help, "help"),
+            _ => return Err(CommandError::Unknown(name)),
+        };
+        if !arguments.is_empty() {
+            return Err(CommandError::UnexpectedArguments(name));
+        }
+        Ok(command)
+    }
+}
+
+fn tickers(arguments: Vec<String>, command: &'static str) -> Result<Vec<String>, CommandError> {
+    if arguments.is_empty() {
+        return Err(CommandError::MissingTickers(command));
+    }
+    Ok(arguments)
+}
+
+/// Reads commands from stdin on a thread of its own, answering on stderr so they do not
+/// mix with the quotes.
+///
+/// The thread is not joined: it blocks on stdin until the process exits. It ends quietly at
+/// the end of the input, so a client without a terminal keeps streaming.
+pub(crate) fn start_command_console(
+    live: Arc<LiveSubscription>,
+    cancellation_token: Arc<CancellationToken>,
+) {
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.parse::<Command>() {
+                Ok(Command::Quit) => {
+                    cancellation_token.cancel();
+                    return;
+                }
+                Ok(command) => execute(command, &live),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    });
+}
+
+fn execute(command: Command, live: &LiveSubscription) {
+    let (change, done) = match command {
+        Command::Add(tickers) => {
+            let done = format!("Added {}", tickers.join(", "));
+            (SubscriptionChange::Add(tickers), done)
+        }
+        Command::Remove(tickers) => {
+            let done = format!("Removed {}", tickers.join(", "));
+            (SubscriptionChange::Remove(tickers), done)
+        }
+        Command::Pause => (SubscriptionChange::Pause, "Paused".to_string()),
+        Command::Resume => (SubscriptionChange::Resume, "Resumed".to_string()),
+        Command::Stats => return show_stats(live),
+        Command::Help => return eprintln!("{}", HELP),
+        Command::Quit => return,
+    };
+    match live.change(change) {
+        Ok(Applied::Live) => eprintln!("{}", done),
+        Ok(Applied::OnResubscribe) => eprintln!("{} once resubscribed", done),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn show_stats(live: &LiveSubscription) {
+    let stats = live.stats();
+    let subscription = live.subscription();
+    match live.server() {
+        Some(server) => eprintln!("Server          {}", server),
+        None => eprintln!("Server          not connected"),
+    }
+    eprintln!(
+        "State           {}",
+        if live.is_paused() {
+            "paused"
+        } else {
+            "streaming"
+        }
+    );
+    eprintln!("Tickers         {}", subscription.tickers().join(", "));
+    if !subscription.groups().is_empty() {
+        eprintln!("Groups          {}", subscription.groups().join(", "));
+    }
+    let changes: Vec<String> = live
+        .changes()
+        .iter()
+        .map(|change| match change {
+            SubscriptionChange::Add(tickers) => format!("+{}", tickers.join(" +")),
+            SubscriptionChange::Remove(tickers) => format!("-{}", tickers.join(" -")),
+            SubscriptionChange::Pause | SubscriptionChange::Resume => String::new(),
+        })
+        .collect();
+    if !changes.is_empty() {
+        eprintln!("Changes         {}", changes.join(" "));
+    }
+    eprintln!("Running for     {:.1?}", stats.elapsed());
+    eprintln!(
+        "Quotes          {} ({:.1}/s)",
+        stats.quotes,
+        stats.quote_rate()
+    );
+    eprintln!("Snapshots       {}", stats.snapshots);
+    eprintln!("Retransmitted   {}", stats.retransmitted);
+    eprintln!("Gaps            {}", stats.gaps);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            "add TSLA, NV*".parse(),
+            Ok(Command::Add(vec!["TSLA".to_string(), "NV*".to_string()]))
+        );
+        assert_eq!(
+            "  REMOVE AAPL ".parse(),
+            Ok(Command::Remove(vec!["AAPL".to_string()]))
+        );
+        assert_eq!("pause".parse(), Ok(Command::Pause));
+        assert_eq!("quit".parse(), Ok(Command::Quit));
+        assert_eq!(
+            "add".parse::<Command>(),
+            Err(CommandError::MissingTickers("add"))
+        );
+        assert_eq!(
+            "stats now".parse::<Command>(),
+            Err(CommandError::UnexpectedArguments("stats"))
+        );
+        assert_eq!(
+            "buy AAPL".parse::<Command>(),
+            Err(CommandError::Unknown("buy".to_string()))
+        );
+    }
+}
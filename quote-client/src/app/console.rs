@@ -0,0 +1,92 @@
+use crate::app::output::{Output, Status};
+use crate::app::quote_format::{QuoteFormat, QuoteKind};
+use quote_client::Event;
+use quote_streaming::StockQuote;
+use std::io::{self, Write};
+
+/// Prints the quotes to stdout and the connection changes to stderr.
+///
+/// Other notices go to stdout along with text quotes, but to stderr when the quotes
+/// are meant for another program.
+pub(crate) struct Console {
+    format: QuoteFormat,
+    header_written: bool,
+}
+
+impl Console {
+    pub(crate) fn new(format: QuoteFormat) -> Self {
+        Self {
+            format,
+            header_written: false,
+        }
+    }
+
+    /// Fails with [`io::ErrorKind::BrokenPipe`] once the reader of stdout went away.
+    pub(crate) fn quote(&mut self, quote: &StockQuote, kind: QuoteKind) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        if !self.header_written {
+            self.header_written = true;
+            if let Some(header) = self.format.header() {
+                writeln!(stdout, "{}", header)?;
+            }
+        }
+        writeln!(stdout, "{}", self.format.format(quote, kind))
+    }
+
+    fn notice(&self, notice: &str) -> io::Result<()> {
+        if self.format.is_text() {
+            writeln!(io::stdout().lock(), "{}", notice)
+        } else {
+            writeln!(io::stderr().lock(), "{}", notice)
+        }
+    }
+}
+
+impl Output for Console {
+    fn event(&mut self, event: Event) -> io::Result<()> {
+        match event {
+            Event::Quote(quote) => self.quote(&quote, QuoteKind::Quote),
+            Event::Snapshot(quote) => self.quote(&quote, QuoteKind::Snapshot),
+            Event::Retransmitted(quote) => self.quote(&quote, QuoteKind::Retransmitted),
+            Event::Gap { from_seq, to_seq } => {
+                eprintln!(
+                    "Missed datagrams {}..={}, requesting retransmission",
+                    from_seq, to_seq
+                );
+                Ok(())
+            }
+            Event::Throttled { max_rate } => self.notice(&format!(
+                "Server throttles quotes to {} per second",
+                max_rate
+            )),
+            Event::ServerError(err) => self.notice(&format!("Server send error: {}", err)),
+            Event::Pong => Ok(()),
+        }
+    }
+
+    fn status(&mut self, status: Status) -> io::Result<()> {
+        match status {
+            Status::Connecting(_) | Status::Subscribed { outage: None, .. } => {}
+            Status::Subscribed {
+                outage: Some(outage),
+                ..
+            } => eprintln!("Resubscribed after an outage of {:.1?}", outage),
+            Status::MulticastGroups(groups) => {
+                for group in groups {
+                    self.notice(&format!(
+                        "Multicast group {}:{} ({})",
+                        group.address(),
+                        group.port(),
+                        group.tickers().join(", ")
+                    ))?;
+                }
+                self.notice("======================")?;
+            }
+            Status::Disconnected { server, error } => eprintln!("{}: {}", server, error),
+            Status::Reconnecting(delay) => eprintln!("Reconnecting in {:.1?}", delay),
+            Status::FailingOver(server) => eprintln!("Failing over to {}", server),
+            Status::FailingBack(server) => eprintln!("Failing back to {}", server),
+        }
+        Ok(())
+    }
+}
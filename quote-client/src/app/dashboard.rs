@@ -0,0 +1,144 @@
+mod status_bar;
+mod ticker_board;
+
+use crate::app::cancellation_token::CancellationToken;
+use crate::app::dashboard::status_bar::StatusBar;
+use crate::app::dashboard::ticker_board::TickerBoard;
+use crate::app::output::{Output, Status};
+use quote_client::Event;
+use ratatui::crossterm::event::{self, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::{DefaultTerminal, Frame};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// How often the dashboard redraws, which also ages the updates.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the streaming thread sends to the dashboard.
+pub(crate) enum Update {
+    Event(Event),
+    Status(Status),
+}
+
+/// Why the dashboard closed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Exit {
+    /// The user quit while the app was still streaming.
+    Quit,
+    /// The streaming thread ended and dropped its feed.
+    StreamEnded,
+}
+
+/// Forwards the app's output to the dashboard.
+pub(crate) struct DashboardFeed(Sender<Update>);
+
+impl DashboardFeed {
+    pub(crate) fn new(updates: Sender<Update>) -> Self {
+        Self(updates)
+    }
+}
+
+impl Output for DashboardFeed {
+    fn event(&mut self, event: Event) -> std::io::Result<()> {
+        // The dashboard is gone once the user quit, and the app is being cancelled
+        let _ = self.0.send(Update::Event(event));
+        Ok(())
+    }
+
+    fn status(&mut self, status: Status) -> std::io::Result<()> {
+        let _ = self.0.send(Update::Status(status));
+        Ok(())
+    }
+}
+
+/// Shows the updates until the user quits or the streaming thread ends.
+pub(crate) fn run_dashboard(
+    updates: Receiver<Update>,
+    cancellation_token: &CancellationToken,
+) -> std::io::Result<Exit> {
+    let mut terminal = ratatui::try_init()?;
+    let result = Dashboard::new().run(&mut terminal, updates, cancellation_token);
+    ratatui::restore();
+    result
+}
+
+struct Dashboard {
+    board: TickerBoard,
+    status_bar: StatusBar,
+}
+
+impl Dashboard {
+    fn new() -> Self {
+        Self {
+            board: TickerBoard::default(),
+            status_bar: StatusBar::new(Instant::now()),
+        }
+    }
+
+    fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        updates: Receiver<Update>,
+        cancellation_token: &CancellationToken,
+    ) -> std::io::Result<Exit> {
+        while !cancellation_token.is_cancelled() {
+            loop {
+                match updates.try_recv() {
+                    Ok(update) => self.apply(update),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return Ok(Exit::StreamEnded),
+                }
+            }
+            self.status_bar.tick(Instant::now());
+            terminal.draw(|frame| self.render(frame))?;
+
+            if event::poll(REFRESH_INTERVAL)?
+                && let event::Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(Exit::Quit),
+                    // Raw mode turns Ctrl-C into a key press instead of a signal
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(Exit::Quit);
+                    }
+                    KeyCode::Char('s') => self.board.next_sort(),
+                    KeyCode::Char('r') => self.board.reverse(),
+                    _ => {}
+                }
+            }
+        }
+        Ok(Exit::Quit)
+    }
+
+    fn apply(&mut self, update: Update) {
+        let now = Instant::now();
+        match update {
+            Update::Event(Event::Pong) => {}
+            Update::Event(event) => {
+                self.status_bar.message();
+                match event {
+                    Event::Quote(quote) | Event::Snapshot(quote) | Event::Retransmitted(quote) => {
+                        self.status_bar.quote();
+                        self.board.update(quote, now);
+                    }
+                    Event::Gap { .. } => self.status_bar.gap(),
+                    Event::Throttled { max_rate } => self.status_bar.throttled(max_rate),
+                    Event::ServerError(_) | Event::Pong => {}
+                }
+            }
+            Update::Status(status) => self.status_bar.status(status),
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [table_area, status_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        frame.render_widget(self.board.table(Instant::now()), table_area);
+
+        let (column, descending) = self.board.sort();
+        let sort = format!("{}{}", column.title(), if descending { " ▼" } else { " ▲" });
+        frame.render_widget(self.status_bar.line(self.board.len(), &sort), status_area);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::app::output::Status;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use std::time::{Duration, Instant};
+
+/// How often the message rate is recomputed.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Connection state and message counts shown below the table.
+pub(crate) struct StatusBar {
+    connection: String,
+    connection_color: Color,
+    messages: u64,
+    quotes: u64,
+    gaps: u64,
+    max_rate: Option<u32>,
+    window_start: Instant,
+    window_messages: u64,
+    rate: f64,
+}
+
+impl StatusBar {
+    pub(crate) fn new(now: Instant) -> Self {
+        Self {
+            connection: "Starting".to_string(),
+            connection_color: Color::Yellow,
+            messages: 0,
+            quotes: 0,
+            gaps: 0,
+            max_rate: None,
+            window_start: now,
+            window_messages: 0,
+            rate: 0.0,
+        }
+    }
+
+    pub(crate) fn status(&mut self, status: Status) {
+        let (connection, color) = match status {
+            Status::Connecting(server) => (format!("Connecting to {}", server), Color::Yellow),
+            Status::Subscribed { server, .. } => (format!("Connected to {}", server), Color::Green),
+            // Keeps the server the subscription was made to
+            Status::MulticastGroups(_) => return,
+            Status::Disconnected { server, error } => (
+                format!("Disconnected from {}: {}", server, error),
+                Color::Red,
+            ),
+            Status::Reconnecting(delay) => {
+                (format!("Reconnecting in {:.1?}", delay), Color::Yellow)
+            }
+            Status::FailingOver(server) => (format!("Failing over to {}", server), Color::Yellow),
+            Status::FailingBack(server) => (format!("Failing back to {}", server), Color::Yellow),
+        };
+        self.connection = connection;
+        self.connection_color = color;
+    }
+
+    pub(crate) fn message(&mut self) {
+        self.messages += 1;
+        self.window_messages += 1;
+    }
+
+    pub(crate) fn quote(&mut self) {
+        self.quotes += 1;
+    }
+
+    pub(crate) fn gap(&mut self) {
+        self.gaps += 1;
+    }
+
+    pub(crate) fn throttled(&mut self, max_rate: u32) {
+        self.max_rate = Some(max_rate);
+    }
+
+    /// Recomputes the message rate once a window has passed.
+    pub(crate) fn tick(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= RATE_WINDOW {
+            self.rate = self.window_messages as f64 / elapsed.as_secs_f64();
+            self.window_start = now;
+            self.window_messages = 0;
+        }
+    }
+
+    pub(crate) fn line(&self, tickers: usize, sort: &str) -> Line<'_> {
+        let mut details = format!(
+            " | {:.1} msg/s | {} messages | {} quotes | {} tickers",
+            self.rate, self.messages, self.quotes, tickers
+        );
+        if self.gaps > 0 {
+            details.push_str(&format!(" | {} gaps", self.gaps));
+        }
+        if let Some(max_rate) = self.max_rate {
+            details.push_str(&format!(" | throttled to {}/s", max_rate));
+        }
+        details.push_str(&format!(
+            " | sorted by {} | s sort, r reverse, q quit",
+            sort
+        ));
+
+        Line::from(vec![
+            Span::styled(
+                format!("● {}", self.connection),
+                Style::default().fg(self.connection_color),
+            ),
+            Span::raw(details),
+        ])
+    }
+}
@@ -0,0 +1,292 @@
+use quote_streaming::StockQuote;
+use ratatui::layout::Constraint;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use ratatui::widgets::{Block, Cell, Row, Table};
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Updates older than this are dimmed.
+const STALE_AGE: Duration = Duration::from_secs(10);
+
+/// Direction of the last price change of a ticker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Tick {
+    Up,
+    Down,
+    Unchanged,
+}
+
+/// The latest state of one ticker.
+pub(crate) struct TickerRow {
+    quote: StockQuote,
+    /// The first price received, which the change is measured from.
+    open: i64,
+    tick: Tick,
+    /// The volume of all quotes received.
+    volume: u64,
+    updated: Instant,
+}
+
+impl TickerRow {
+    fn new(quote: StockQuote, now: Instant) -> Self {
+        Self {
+            open: quote.price(),
+            tick: Tick::Unchanged,
+            volume: u64::from(quote.volume()),
+            updated: now,
+            quote,
+        }
+    }
+
+    fn update(&mut self, quote: StockQuote, now: Instant) {
+        self.tick = match quote.price().cmp(&self.quote.price()) {
+            Ordering::Greater => Tick::Up,
+            Ordering::Less => Tick::Down,
+            Ordering::Equal => Tick::Unchanged,
+        };
+        self.volume += u64::from(quote.volume());
+        self.updated = now;
+        self.quote = quote;
+    }
+
+    pub(crate) fn ticker(&self) -> &str {
+        self.quote.ticker()
+    }
+
+    pub(crate) fn last(&self) -> Decimal {
+        Decimal::new(self.quote.price(), 2)
+    }
+
+    pub(crate) fn change(&self) -> Decimal {
+        Decimal::new(self.quote.price() - self.open, 2)
+    }
+
+    /// The change in percent of the first price, `None` when that was zero.
+    pub(crate) fn change_percent(&self) -> Option<Decimal> {
+        let change = Decimal::from(self.quote.price() - self.open) * Decimal::ONE_HUNDRED;
+        change
+            .checked_div(Decimal::from(self.open))
+            .map(|percent| percent.round_dp(2))
+    }
+
+    pub(crate) fn tick(&self) -> Tick {
+        self.tick
+    }
+
+    pub(crate) fn volume(&self) -> u64 {
+        self.volume
+    }
+
+    pub(crate) fn age(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.updated)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SortColumn {
+    Ticker,
+    Last,
+    Change,
+    ChangePercent,
+    Volume,
+    Age,
+}
+
+impl SortColumn {
+    const ALL: [SortColumn; 6] = [
+        SortColumn::Ticker,
+        SortColumn::Last,
+        SortColumn::Change,
+        SortColumn::ChangePercent,
+        SortColumn::Volume,
+        SortColumn::Age,
+    ];
+
+    fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|column| *column == self);
+        Self::ALL[index.map_or(0, |index| (index + 1) % Self::ALL.len())]
+    }
+
+    pub(crate) fn title(self) -> &'static str {
+        match self {
+            SortColumn::Ticker => "Ticker",
+            SortColumn::Last => "Last",
+            SortColumn::Change => "Change",
+            SortColumn::ChangePercent => "Change %",
+            SortColumn::Volume => "Volume",
+            SortColumn::Age => "Age",
+        }
+    }
+
+    fn compare(self, a: &TickerRow, b: &TickerRow) -> Ordering {
+        match self {
+            SortColumn::Ticker => a.ticker().cmp(b.ticker()),
+            SortColumn::Last => a.last().cmp(&b.last()),
+            SortColumn::Change => a.change().cmp(&b.change()),
+            SortColumn::ChangePercent => a.change_percent().cmp(&b.change_percent()),
+            SortColumn::Volume => a.volume().cmp(&b.volume()),
+            // The oldest update has the greatest age
+            SortColumn::Age => b.updated.cmp(&a.updated),
+        }
+    }
+}
+
+/// The latest quote of every ticker, in the order the user chose.
+pub(crate) struct TickerBoard {
+    rows: HashMap<String, TickerRow>,
+    sort: SortColumn,
+    descending: bool,
+}
+
+impl Default for TickerBoard {
+    fn default() -> Self {
+        Self {
+            rows: HashMap::new(),
+            sort: SortColumn::Ticker,
+            descending: false,
+        }
+    }
+}
+
+impl TickerBoard {
+    pub(crate) fn update(&mut self, quote: StockQuote, now: Instant) {
+        match self.rows.get_mut(quote.ticker()) {
+            Some(row) => row.update(quote, now),
+            None => {
+                self.rows
+                    .insert(quote.ticker().to_string(), TickerRow::new(quote, now));
+            }
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Sorts by the next column, in ascending order.
+    pub(crate) fn next_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.descending = false;
+    }
+
+    pub(crate) fn reverse(&mut self) {
+        self.descending = !self.descending;
+    }
+
+    pub(crate) fn sort(&self) -> (SortColumn, bool) {
+        (self.sort, self.descending)
+    }
+
+    pub(crate) fn sorted_rows(&self) -> Vec<&TickerRow> {
+        let mut rows: Vec<_> = self.rows.values().collect();
+        rows.sort_by(|a, b| {
+            // Ties keep a stable order by ticker
+            let order = self
+                .sort
+                .compare(a, b)
+                .then_with(|| a.ticker().cmp(b.ticker()));
+            if self.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        rows
+    }
+
+    pub(crate) fn table(&self, now: Instant) -> Table<'_> {
+        let header = Row::new(SortColumn::ALL.map(|column| {
+            let marker = match (column == self.sort, self.descending) {
+                (true, false) => " ▲",
+                (true, true) => " ▼",
+                (false, _) => "",
+            };
+            Cell::from(format!("{}{}", column.title(), marker))
+        }))
+        .style(Style::default().add_modifier(Modifier::BOLD));
+
+        let rows = self.sorted_rows().into_iter().map(|row| {
+            let (arrow, tick_color) = match row.tick() {
+                Tick::Up => ("▲", Color::Green),
+                Tick::Down => ("▼", Color::Red),
+                Tick::Unchanged => (" ", Color::Reset),
+            };
+            let change_color = match row.change().cmp(&Decimal::ZERO) {
+                Ordering::Greater => Color::Green,
+                Ordering::Less => Color::Red,
+                Ordering::Equal => Color::Reset,
+            };
+            let age = row.age(now);
+            let age_style = if age >= STALE_AGE {
+                Style::default().add_modifier(Modifier::DIM)
+            } else {
+                Style::default()
+            };
+
+            Row::new([
+                Cell::from(row.ticker().to_string()),
+                Cell::from(Span::styled(
+                    format!("{} {:>10}", arrow, row.last()),
+                    Style::default().fg(tick_color),
+                )),
+                Cell::from(Span::styled(
+                    format!("{:>+10}", row.change()),
+                    Style::default().fg(change_color),
+                )),
+                Cell::from(Span::styled(
+                    row.change_percent()
+                        .map_or_else(|| "-".to_string(), |percent| format!("{:>+8.2}%", percent)),
+                    Style::default().fg(change_color),
+                )),
+                Cell::from(format!("{:>12}", row.volume())),
+                Cell::from(Span::styled(format!("{:>6.1?}", age), age_style)),
+            ])
+        });
+
+        Table::new(
+            rows,
+            [
+                Constraint::Length(10),
+                Constraint::Length(14),
+                Constraint::Length(12),
+                Constraint::Length(12),
+                Constraint::Length(14),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title(" Quotes "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_by_change() {
+        let now = Instant::now();
+        let mut board = TickerBoard::default();
+        board.update(StockQuote::new("AAPL", 10000, 10, 0), now);
+        board.update(StockQuote::new("MSFT", 20000, 10, 0), now);
+        board.update(StockQuote::new("AAPL", 11000, 5, 0), now);
+        board.update(StockQuote::new("MSFT", 19000, 5, 0), now);
+
+        let aapl = &board.sorted_rows()[0];
+        assert_eq!(aapl.tick(), Tick::Up);
+        assert_eq!(aapl.change(), Decimal::new(1000, 2));
+        assert_eq!(aapl.change_percent(), Some(Decimal::TEN));
+        assert_eq!(aapl.volume(), 15);
+
+        board.next_sort();
+        board.next_sort();
+        assert_eq!(board.sort(), (SortColumn::Change, false));
+        let tickers: Vec<_> = board.sorted_rows().iter().map(|row| row.ticker()).collect();
+        assert_eq!(tickers, ["MSFT", "AAPL"]);
+        board.reverse();
+        assert_eq!(board.sorted_rows()[0].ticker(), "AAPL");
+    }
+}
@@ -1,16 +1,44 @@
-use crate::app::read_udp_response::ReadUdpResponseError;
-use crate::app::server_connect::ServerConnectError;
+use crate::app::alerts::AlertError;
+use crate::app::recording::RecordingError;
+use quote_client::{ServerConnectError, SubscriberError};
 
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum AppError {
     #[error("Error setting Ctrl-C handler")]
     Ctrlc(#[from] ctrlc::Error),
-    #[error("Failed to create UDP socket")]
-    UdpSocket(#[from] std::io::Error),
     #[error("Failed to connect to server: {0}")]
     ServerConnect(#[from] ServerConnectError),
-    #[error("Failed to read UDP response: {0}")]
-    ReadResponse(#[from] ReadUdpResponseError),
+    #[error("{0}")]
+    Subscriber(#[from] SubscriberError),
+    #[error("No server to connect to")]
+    NoServers,
     #[error("Failed to join thread: {0}")]
     JoinThread(String),
+    #[error("Terminal error: {0}")]
+    Terminal(std::io::Error),
+    #[error("Failed to write output: {0}")]
+    Output(std::io::Error),
+    #[error("Recording error: {0}")]
+    Recording(#[from] RecordingError),
+    #[error("Alert error: {0}")]
+    Alerts(#[from] AlertError),
+}
+
+impl AppError {
+    /// Returns `true` when the server went away and subscribing again may succeed.
+    pub(crate) fn is_disconnection(&self) -> bool {
+        match self {
+            AppError::ServerConnect(ServerConnectError::Connection(_)) => true,
+            AppError::Subscriber(e) => e.is_disconnection(),
+            _ => false,
+        }
+    }
+}
+
+/// Ends quietly once the reader of stdout went away, as with `--format json | head`.
+pub(crate) fn closed_output_ends(result: Result<(), AppError>) -> Result<(), AppError> {
+    match result {
+        Err(AppError::Output(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
 }
@@ -0,0 +1,43 @@
+use crate::app::cancellation_token::CancellationToken;
+use crate::app::server::Server;
+use quote_client::list_groups;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SLEEP_STEP: Duration = Duration::from_millis(100);
+
+/// Probes the servers preferred over the current one while streaming from it.
+///
+/// The thread cancels the session and returns the index of the first preferred server that
+/// answers a request, or `None` when the session ended for another reason.
+pub(super) fn start_fail_back_probe(
+    session_token: Arc<CancellationToken>,
+    preferred: Vec<Server>,
+    interval: Duration,
+) -> thread::JoinHandle<Option<usize>> {
+    thread::spawn(move || probe(session_token, preferred, interval))
+}
+
+fn probe(
+    session_token: Arc<CancellationToken>,
+    preferred: Vec<Server>,
+    interval: Duration,
+) -> Option<usize> {
+    let mut next_probe = Instant::now() + interval;
+    while !session_token.is_cancelled() {
+        if Instant::now() < next_probe {
+            thread::sleep(SLEEP_STEP);
+            continue;
+        }
+
+        for (index, server) in preferred.iter().enumerate() {
+            if list_groups(server.address(), server.port()).is_ok() {
+                session_token.cancel();
+                return Some(index);
+            }
+        }
+        next_probe = Instant::now() + interval;
+    }
+    None
+}
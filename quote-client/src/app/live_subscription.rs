@@ -0,0 +1,167 @@
+use crate::app::server::Server;
+use quote_client::{Event, SubscriberError, Subscription, SubscriptionController};
+use quote_streaming::SubscriptionChange;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The subscription as changed from the command console, shared with the streaming thread
+/// so resubscribing after an outage keeps the changes.
+pub(crate) struct LiveSubscription {
+    state: Mutex<State>,
+}
+
+struct State {
+    /// What a new stream subscribes with, before the changes.
+    subscription: Subscription,
+    /// The added and removed tickers, in order. They are replayed on every new stream, as a
+    /// removed pattern or group member cannot be left out of the subscription itself.
+    changes: Vec<SubscriptionChange>,
+    paused: bool,
+    /// The controller of the running stream, `None` while there is none.
+    controller: Option<SubscriptionController>,
+    server: Option<Server>,
+    stats: Stats,
+}
+
+/// What the client received since it started.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Stats {
+    pub(crate) started: Instant,
+    pub(crate) quotes: u64,
+    pub(crate) snapshots: u64,
+    pub(crate) retransmitted: u64,
+    pub(crate) gaps: u64,
+}
+
+/// Whether a change reached the server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Applied {
+    Live,
+    /// No stream is running; the change applies to the next subscription.
+    OnResubscribe,
+}
+
+impl LiveSubscription {
+    pub(crate) fn new(subscription: Subscription) -> Self {
+        Self {
+            state: Mutex::new(State {
+                subscription,
+                changes: Vec::new(),
+                paused: false,
+                controller: None,
+                server: None,
+                stats: Stats {
+                    started: Instant::now(),
+                    quotes: 0,
+                    snapshots: 0,
+                    retransmitted: 0,
+                    gaps: 0,
+                },
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn subscription(&self) -> Subscription {
+        self.state().subscription.clone()
+    }
+
+    /// The tickers added and removed since the client started.
+    pub(crate) fn changes(&self) -> Vec<SubscriptionChange> {
+        self.state().changes.clone()
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.state().paused
+    }
+
+    pub(crate) fn server(&self) -> Option<Server> {
+        self.state().server.clone()
+    }
+
+    pub(crate) fn stats(&self) -> Stats {
+        self.state().stats.clone()
+    }
+
+    /// Takes the controller of a new stream, applying the changes made to the earlier ones and
+    /// pausing it again if the user paused the last one.
+    pub(crate) fn subscribed(
+        &self,
+        controller: SubscriptionController,
+        server: Server,
+    ) -> Result<(), SubscriberError> {
+        let (changes, paused) = {
+            let mut state = self.state();
+            state.controller = Some(controller.clone());
+            state.server = Some(server);
+            (state.changes.clone(), state.paused)
+        };
+        for change in changes {
+            controller.change(change)?;
+        }
+        if paused {
+            controller.pause()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn disconnected(&self) {
+        let mut state = self.state();
+        state.controller = None;
+        state.server = None;
+    }
+
+    pub(crate) fn received(&self, event: &Event) {
+        let stats = &mut self.state().stats;
+        match event {
+            Event::Quote(_) => stats.quotes += 1,
+            Event::Snapshot(_) => stats.snapshots += 1,
+            Event::Retransmitted(_) => stats.retransmitted += 1,
+            Event::Gap { .. } => stats.gaps += 1,
+            Event::Throttled { .. } | Event::ServerError(_) | Event::Pong => {}
+        }
+    }
+
+    /// Sends the change to the running stream, and keeps it for later subscriptions once the
+    /// server took it.
+    pub(crate) fn change(&self, change: SubscriptionChange) -> Result<Applied, SubscriberError> {
+        // The request may wait for the server, so the stream must not wait for the lock
+        let controller = self.state().controller.clone();
+        let applied = match controller {
+            Some(controller) => {
+                controller.change(change.clone())?;
+                Applied::Live
+            }
+            None => Applied::OnResubscribe,
+        };
+
+        let mut state = self.state();
+        match change {
+            SubscriptionChange::Add(_) | SubscriptionChange::Remove(_) => {
+                state.changes.push(change)
+            }
+            SubscriptionChange::Pause => state.paused = true,
+            SubscriptionChange::Resume => state.paused = false,
+        }
+        Ok(applied)
+    }
+}
+
+impl Stats {
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// New quotes per second since the client started.
+    pub(crate) fn quote_rate(&self) -> f64 {
+        let elapsed = self.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.quotes as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+}
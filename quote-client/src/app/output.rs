@@ -0,0 +1,32 @@
+use crate::app::server::Server;
+use quote_client::Event;
+use quote_streaming::MulticastGroup;
+use std::time::Duration;
+
+/// Changes of the connection, reported besides the streamed events.
+#[derive(Debug, Clone)]
+pub(crate) enum Status {
+    Connecting(Server),
+    Subscribed {
+        server: Server,
+        /// How long the client was without a subscription, if it lost one.
+        outage: Option<Duration>,
+    },
+    MulticastGroups(Vec<MulticastGroup>),
+    Disconnected {
+        server: Server,
+        error: String,
+    },
+    Reconnecting(Duration),
+    FailingOver(Server),
+    FailingBack(Server),
+}
+
+/// Where the app reports what it receives.
+///
+/// An error ends the app; a [`std::io::ErrorKind::BrokenPipe`] ends it cleanly.
+pub(crate) trait Output {
+    fn event(&mut self, event: Event) -> std::io::Result<()>;
+
+    fn status(&mut self, status: Status) -> std::io::Result<()>;
+}
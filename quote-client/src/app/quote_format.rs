@@ -0,0 +1,225 @@
+use chrono::{DateTime, SecondsFormat};
+use quote_streaming::StockQuote;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// How a quote reached the client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum QuoteKind {
+    Quote,
+    Snapshot,
+    Retransmitted,
+}
+
+impl QuoteKind {
+    fn name(self) -> &'static str {
+        match self {
+            QuoteKind::Quote => "quote",
+            QuoteKind::Snapshot => "snapshot",
+            QuoteKind::Retransmitted => "retransmitted",
+        }
+    }
+}
+
+/// How quotes are written to stdout.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) enum QuoteFormat {
+    /// The `Display` of the quote, meant for people.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    JsonLines,
+    /// Comma-separated values after a header line.
+    Csv,
+    /// A user-defined line with `{field}` placeholders.
+    Template(Template),
+}
+
+impl QuoteFormat {
+    /// Returns `true` for the format meant for people, which may be mixed with notices.
+    pub(crate) fn is_text(&self) -> bool {
+        matches!(self, QuoteFormat::Text)
+    }
+
+    /// The line to write before the first quote.
+    pub(crate) fn header(&self) -> Option<&'static str> {
+        match self {
+            QuoteFormat::Csv => Some("ticker,price,volume,timestamp,kind"),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn format(&self, quote: &StockQuote, kind: QuoteKind) -> String {
+        match self {
+            QuoteFormat::Text => match kind {
+                QuoteKind::Quote => quote.to_string(),
+                kind => format!("{} ({})", quote, kind.name()),
+            },
+            QuoteFormat::JsonLines => format!(
+                r#"{{"ticker":{},"price":{},"volume":{},"timestamp":{},"kind":"{}"}}"#,
+                serde_json::Value::from(quote.ticker()),
+                price(quote),
+                quote.volume(),
+                serde_json::Value::from(timestamp(quote)),
+                kind.name()
+            ),
+            QuoteFormat::Csv => format!(
+                "{},{},{},{},{}",
+                csv_field(quote.ticker()),
+                price(quote),
+                quote.volume(),
+                timestamp(quote),
+                kind.name()
+            ),
+            QuoteFormat::Template(template) => template.render(quote, kind),
+        }
+    }
+}
+
+/// The price in currency units, always with two decimal places.
+pub(crate) fn price(quote: &StockQuote) -> Decimal {
+    Decimal::new(quote.price(), 2)
+}
+
+/// The quote time in RFC 3339, in UTC, or the Unix timestamp when it is out of range.
+pub(crate) fn timestamp(quote: &StockQuote) -> String {
+    DateTime::from_timestamp(quote.timestamp(), 0).map_or_else(
+        || quote.timestamp().to_string(),
+        |time| time.to_rfc3339_opts(SecondsFormat::Secs, true),
+    )
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub(crate) enum TemplateError {
+    #[error("unknown field `{{{0}}}`, expected ticker, price, volume, timestamp or kind")]
+    UnknownField(String),
+    #[error("unclosed `{{`, write `{{{{` for a literal brace")]
+    Unclosed,
+    #[error("unmatched `}}`, write `}}}}` for a literal brace")]
+    Unmatched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Ticker,
+    Price,
+    Volume,
+    Timestamp,
+    Kind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+/// A line of text with `{ticker}`, `{price}`, `{volume}`, `{timestamp}` and `{kind}` placeholders.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Template(Vec<Piece>);
+
+impl Template {
+    fn render(&self, quote: &StockQuote, kind: QuoteKind) -> String {
+        let mut line = String::new();
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(text) => line.push_str(text),
+                Piece::Field(Field::Ticker) => line.push_str(quote.ticker()),
+                Piece::Field(Field::Price) => line.push_str(&price(quote).to_string()),
+                Piece::Field(Field::Volume) => line.push_str(&quote.volume().to_string()),
+                Piece::Field(Field::Timestamp) => line.push_str(&timestamp(quote)),
+                Piece::Field(Field::Kind) => line.push_str(kind.name()),
+            }
+        }
+        line
+    }
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = value.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(TemplateError::Unmatched),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(TemplateError::Unclosed),
+                        }
+                    }
+                    let field = match name.trim() {
+                        "ticker" => Field::Ticker,
+                        "price" => Field::Price,
+                        "volume" => Field::Volume,
+                        "timestamp" => Field::Timestamp,
+                        "kind" => Field::Kind,
+                        _ => return Err(TemplateError::UnknownField(name)),
+                    };
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Field(field));
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Self(pieces))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_quote() {
+        // 2025-01-31T10:00:00Z
+        let quote = StockQuote::new("AAPL", 12340, 100, 1738317600);
+
+        assert_eq!(
+            QuoteFormat::JsonLines.format(&quote, QuoteKind::Snapshot),
+            r#"{"ticker":"AAPL","price":123.40,"volume":100,"timestamp":"2025-01-31T10:00:00Z","kind":"snapshot"}"#
+        );
+        assert_eq!(
+            QuoteFormat::Csv.format(&quote, QuoteKind::Quote),
+            "AAPL,123.40,100,2025-01-31T10:00:00Z,quote"
+        );
+
+        let template: Template = "{ticker}\t{price} {{{volume}}}".parse().unwrap();
+        assert_eq!(
+            QuoteFormat::Template(template).format(&quote, QuoteKind::Quote),
+            "AAPL\t123.40 {100}"
+        );
+        assert_eq!(
+            "{bid}".parse::<Template>(),
+            Err(TemplateError::UnknownField("bid".to_string()))
+        );
+        assert_eq!("{price".parse::<Template>(), Err(TemplateError::Unclosed));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::app::cancellation_token::CancellationToken;
+use crate::app::server::Server;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const INITIAL_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+const SLEEP_STEP: Duration = Duration::from_millis(100);
+
+/// Chooses the server for the next attempt, paces attempts with exponential backoff and
+/// measures the outage.
+pub(crate) struct Reconnect {
+    /// Ordered by priority, the preferred server first.
+    servers: Vec<Server>,
+    current: usize,
+    /// The number of servers that failed since the last successful subscription.
+    failures: usize,
+    attempt: u32,
+    outage_start: Option<Instant>,
+}
+
+impl Reconnect {
+    pub(crate) fn new(mut servers: Vec<Server>) -> Self {
+        servers.sort_by_key(Server::priority);
+        Self {
+            servers,
+            current: 0,
+            failures: 0,
+            attempt: 0,
+            outage_start: None,
+        }
+    }
+
+    /// The server to subscribe to.
+    pub(crate) fn server(&self) -> &Server {
+        &self.servers[self.current]
+    }
+
+    /// The servers preferred over the current one.
+    pub(crate) fn preferred(&self) -> &[Server] {
+        &self.servers[..self.current]
+    }
+
+    /// Moves on to the next server after the current one failed.
+    ///
+    /// Returns `true` when every server failed since the last successful subscription, so the
+    /// next attempt should wait.
+    pub(crate) fn fail_over(&mut self) -> bool {
+        self.current = (self.current + 1) % self.servers.len();
+        self.failures += 1;
+        if self.failures < self.servers.len() {
+            return false;
+        }
+        self.failures = 0;
+        true
+    }
+
+    /// Switches back to one of the [`preferred`](Self::preferred) servers.
+    pub(crate) fn fail_back(&mut self, index: usize) {
+        self.current = index.min(self.current);
+    }
+
+    /// Marks the start of an outage, unless one is already in progress.
+    pub(crate) fn disconnected(&mut self) {
+        self.outage_start.get_or_insert_with(Instant::now);
+    }
+
+    /// Ends the outage after a successful subscription and resets the backoff.
+    ///
+    /// Returns how long the outage lasted, if there was one.
+    pub(crate) fn subscribed(&mut self) -> Option<Duration> {
+        self.failures = 0;
+        self.attempt = 0;
+        self.outage_start
+            .take()
+            .map(|outage_start| outage_start.elapsed())
+    }
+
+    /// Returns how long to wait before the next attempt, longer with every call.
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let delay = jitter(backoff(self.attempt));
+        self.attempt = self.attempt.saturating_add(1);
+        delay
+    }
+}
+
+/// Waits for the delay; returns `false` when cancelled meanwhile.
+pub(crate) fn wait(delay: Duration, cancellation_token: &CancellationToken) -> bool {
+    let deadline = Instant::now() + delay;
+    while !cancellation_token.is_cancelled() {
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        thread::sleep(SLEEP_STEP.min(deadline - now));
+    }
+    false
+}
+
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_DELAY)
+}
+
+/// Spreads the delay over its upper half, so clients dropped together do not return together.
+fn jitter(delay: Duration) -> Duration {
+    delay.mul_f64(rand::random_range(0.5..=1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fail_over_by_priority() {
+        let server = |port, priority| Server::new("127.0.0.1".parse().unwrap(), port, priority);
+        let mut reconnect = Reconnect::new(vec![server(1, 2), server(2, 1), server(3, 2)]);
+        assert_eq!(reconnect.server().port(), 2);
+
+        assert!(!reconnect.fail_over());
+        assert_eq!(reconnect.server().port(), 1);
+        assert_eq!(reconnect.preferred(), &[server(2, 1)]);
+        assert!(!reconnect.fail_over());
+        assert!(reconnect.fail_over());
+        assert_eq!(reconnect.server().port(), 2);
+
+        reconnect.fail_over();
+        reconnect.subscribed();
+        reconnect.fail_back(0);
+        assert_eq!(reconnect.server().port(), 2);
+        assert!(!reconnect.fail_over());
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0), INITIAL_DELAY);
+        assert_eq!(backoff(3), INITIAL_DELAY * 8);
+        assert_eq!(backoff(40), MAX_DELAY);
+
+        let delay = jitter(MAX_DELAY);
+        assert!(delay >= MAX_DELAY / 2 && delay <= MAX_DELAY);
+    }
+}
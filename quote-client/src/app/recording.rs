@@ -0,0 +1,339 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use quote_client::Event;
+use quote_streaming::{StockQuote, read_frame, write_frame};
+use rkyv::rancor;
+use rkyv::util::AlignedVec;
+use rust_decimal::Decimal;
+use serde::{Deserializer, Serializer};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The start of a binary recording, followed by the format version.
+const MAGIC: &[u8; 4] = b"QREC";
+const VERSION: u8 = 1;
+
+/// How the events of a recording are stored.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub(crate) enum Encoding {
+    /// Length-prefixed rkyv records after a short header
+    Binary,
+    /// One JSON object per line
+    #[value(alias = "json")]
+    Jsonl,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RecordingError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid binary record: {0}")]
+    Binary(#[from] rancor::Error),
+    #[error("Invalid JSON record: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unsupported recording version {0}")]
+    Version(u8),
+}
+
+/// An event as it was received, with the time it arrived.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub(crate) struct Record {
+    /// The receive time, in microseconds since the Unix epoch.
+    #[serde(
+        serialize_with = "micros_to_rfc3339",
+        deserialize_with = "rfc3339_to_micros"
+    )]
+    received: i64,
+    /// The sequence number of the datagram or frame the event came in.
+    sequence: u64,
+    #[serde(flatten)]
+    event: RecordedEvent,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum RecordedEvent {
+    Quote(RecordedQuote),
+    Snapshot(RecordedQuote),
+    Retransmitted(RecordedQuote),
+    Gap { from_seq: u64, to_seq: u64 },
+    Throttled { max_rate: u32 },
+    ServerError { message: String },
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+struct RecordedQuote {
+    ticker: String,
+    /// The price in cents, written as a decimal in JSON.
+    #[serde(
+        serialize_with = "cents_to_decimal",
+        deserialize_with = "decimal_to_cents"
+    )]
+    price: i64,
+    volume: u32,
+    #[serde(
+        serialize_with = "secs_to_rfc3339",
+        deserialize_with = "rfc3339_to_secs"
+    )]
+    timestamp: i64,
+}
+
+impl From<StockQuote> for RecordedQuote {
+    fn from(quote: StockQuote) -> Self {
+        Self {
+            ticker: quote.ticker().to_string(),
+            price: quote.price(),
+            volume: quote.volume(),
+            timestamp: quote.timestamp(),
+        }
+    }
+}
+
+impl From<RecordedQuote> for StockQuote {
+    fn from(quote: RecordedQuote) -> Self {
+        StockQuote::new(&quote.ticker, quote.price, quote.volume, quote.timestamp)
+    }
+}
+
+impl Record {
+    /// Returns `None` for keepalive pongs, which are not recorded.
+    pub(crate) fn new(received: DateTime<Utc>, sequence: u64, event: Event) -> Option<Self> {
+        let event = match event {
+            Event::Quote(quote) => RecordedEvent::Quote(quote.into()),
+            Event::Snapshot(quote) => RecordedEvent::Snapshot(quote.into()),
+            Event::Retransmitted(quote) => RecordedEvent::Retransmitted(quote.into()),
+            Event::Gap { from_seq, to_seq } => RecordedEvent::Gap { from_seq, to_seq },
+            Event::Throttled { max_rate } => RecordedEvent::Throttled { max_rate },
+            Event::ServerError(message) => RecordedEvent::ServerError { message },
+            Event::Pong => return None,
+        };
+        Some(Self {
+            received: received.timestamp_micros(),
+            sequence,
+            event,
+        })
+    }
+
+    /// The receive time, in microseconds since the Unix epoch.
+    pub(crate) fn received(&self) -> i64 {
+        self.received
+    }
+
+    pub(crate) fn into_event(self) -> Event {
+        match self.event {
+            RecordedEvent::Quote(quote) => Event::Quote(quote.into()),
+            RecordedEvent::Snapshot(quote) => Event::Snapshot(quote.into()),
+            RecordedEvent::Retransmitted(quote) => Event::Retransmitted(quote.into()),
+            RecordedEvent::Gap { from_seq, to_seq } => Event::Gap { from_seq, to_seq },
+            RecordedEvent::Throttled { max_rate } => Event::Throttled { max_rate },
+            RecordedEvent::ServerError { message } => Event::ServerError(message),
+        }
+    }
+}
+
+/// Appends records to a recording file.
+pub(crate) struct RecordWriter {
+    writer: BufWriter<File>,
+    encoding: Encoding,
+}
+
+impl RecordWriter {
+    pub(crate) fn create(path: &Path, encoding: Encoding) -> Result<Self, RecordingError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if encoding == Encoding::Binary {
+            writer.write_all(MAGIC)?;
+            writer.write_all(&[VERSION])?;
+        }
+        Ok(Self { writer, encoding })
+    }
+
+    /// Writes the record through to the file, so an interrupted recording keeps it.
+    pub(crate) fn write(&mut self, record: &Record) -> Result<(), RecordingError> {
+        match self.encoding {
+            Encoding::Binary => {
+                let bytes = rkyv::to_bytes::<rancor::Error>(record)?;
+                write_frame(&mut self.writer, &bytes)?;
+            }
+            Encoding::Jsonl => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                self.writer.write_all(b"\n")?;
+            }
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads the records of a recording file, telling the encoding by its header.
+pub(crate) struct RecordReader {
+    reader: BufReader<File>,
+    encoding: Encoding,
+}
+
+impl RecordReader {
+    pub(crate) fn open(path: &Path) -> Result<Self, RecordingError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let encoding = if reader.fill_buf()?.starts_with(MAGIC) {
+            let mut header = [0; MAGIC.len() + 1];
+            reader.read_exact(&mut header)?;
+            match header[MAGIC.len()] {
+                VERSION => Encoding::Binary,
+                version => return Err(RecordingError::Version(version)),
+            }
+        } else {
+            Encoding::Jsonl
+        };
+        Ok(Self { reader, encoding })
+    }
+
+    fn read(&mut self) -> Result<Option<Record>, RecordingError> {
+        match self.encoding {
+            Encoding::Binary => {
+                if self.reader.fill_buf()?.is_empty() {
+                    return Ok(None);
+                }
+                // Archived records must be read from aligned memory
+                let mut bytes = AlignedVec::<16>::new();
+                bytes.extend_from_slice(&read_frame(&mut self.reader)?);
+                Ok(Some(rkyv::from_bytes::<Record, rancor::Error>(&bytes)?))
+            }
+            Encoding::Jsonl => loop {
+                let mut line = String::new();
+                if self.reader.read_line(&mut line)? == 0 {
+                    return Ok(None);
+                }
+                if !line.trim().is_empty() {
+                    return Ok(Some(serde_json::from_str(&line)?));
+                }
+            },
+        }
+    }
+}
+
+impl Iterator for RecordReader {
+    type Item = Result<Record, RecordingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+fn micros_to_rfc3339<S: Serializer>(micros: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    match DateTime::from_timestamp_micros(*micros) {
+        Some(time) => serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Micros, true)),
+        None => serializer.serialize_i64(*micros),
+    }
+}
+
+fn rfc3339_to_micros<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let time: String = serde::Deserialize::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&time)
+        .map(|time| time.timestamp_micros())
+        .map_err(serde::de::Error::custom)
+}
+
+fn secs_to_rfc3339<S: Serializer>(secs: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    match DateTime::from_timestamp(*secs, 0) {
+        Some(time) => serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        None => serializer.serialize_i64(*secs),
+    }
+}
+
+fn rfc3339_to_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let time: String = serde::Deserialize::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&time)
+        .map(|time| time.timestamp())
+        .map_err(serde::de::Error::custom)
+}
+
+fn cents_to_decimal<S: Serializer>(cents: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&Decimal::new(*cents, 2).to_string())
+}
+
+fn decimal_to_cents<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let price: String = serde::Deserialize::deserialize(deserializer)?;
+    let mut price: Decimal = price.parse().map_err(serde::de::Error::custom)?;
+    price.rescale(2);
+    i64::try_from(price.mantissa()).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_written_records() {
+        let received = DateTime::from_timestamp_micros(1_738_317_600_123_456).unwrap();
+        let records = vec![
+            Record::new(
+                received,
+                7,
+                Event::Quote(StockQuote::new("AAPL", 12340, 100, 1738317600)),
+            )
+            .unwrap(),
+            Record::new(
+                received,
+                9,
+                Event::Gap {
+                    from_seq: 8,
+                    to_seq: 8,
+                },
+            )
+            .unwrap(),
+        ];
+        assert_eq!(Record::new(received, 0, Event::Pong), None);
+
+        for encoding in [Encoding::Binary, Encoding::Jsonl] {
+            let path = std::env::temp_dir().join(format!(
+                "quote-client-recording-{}-{:?}",
+                std::process::id(),
+                encoding
+            ));
+            let mut writer = RecordWriter::create(&path, encoding).unwrap();
+            for record in &records {
+                writer.write(record).unwrap();
+            }
+            drop(writer);
+
+            let read: Vec<_> = RecordReader::open(&path)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            if encoding == Encoding::Jsonl {
+                let first_line = std::fs::read_to_string(&path).unwrap();
+                assert!(first_line.starts_with(
+                    r#"{"received":"2025-01-31T10:00:00.123456Z","sequence":7,"kind":"quote","ticker":"AAPL","price":"123.40","#
+                ));
+            }
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(read, records);
+        }
+    }
+}
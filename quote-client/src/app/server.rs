@@ -0,0 +1,38 @@
+use std::net::{IpAddr, SocketAddr};
+
+/// A quote server the client may stream from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Server {
+    address: IpAddr,
+    port: u16,
+    /// Servers with lower values are preferred.
+    priority: u32,
+}
+
+impl Server {
+    pub(crate) fn new(address: IpAddr, port: u16, priority: u32) -> Self {
+        Self {
+            address,
+            port,
+            priority,
+        }
+    }
+
+    pub(crate) fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub(crate) fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub(crate) fn priority(&self) -> u32 {
+        self.priority
+    }
+}
+
+impl std::fmt::Display for Server {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", SocketAddr::new(self.address, self.port))
+    }
+}
@@ -1,27 +1,98 @@
+use crate::app::{Encoding, Server, Template};
+use chrono::DateTime;
 use clap::Subcommand;
-use std::net::IpAddr;
+use quote_streaming::QuoteFilter;
+use rust_decimal::Decimal;
+use std::net::{IpAddr, SocketAddr};
 
 #[derive(Debug, clap::Parser)]
 pub(super) struct Args {
-    /// Tickers source
+    /// Tickers source or command
     #[command(subcommand)]
-    pub tickers: TickersSource,
+    pub command: Command,
 
-    /// Server port
-    #[clap(short = 'p', long)]
-    pub server_port: u16,
+    /// Server port, required unless `--server` is given or a recording is replayed
+    #[clap(short = 'p', long, requires = "server_address")]
+    pub server_port: Option<u16>,
 
-    /// Server address
-    #[clap(short = 'a', long)]
-    pub server_address: IpAddr,
+    /// Server address, required unless `--server` is given or a recording is replayed
+    #[clap(short = 'a', long, requires = "server_port")]
+    pub server_address: Option<IpAddr>,
+
+    /// Additional server as `ADDRESS:PORT[@PRIORITY]`, lower priorities are preferred
+    #[clap(long = "server", value_parser = parse_server)]
+    pub servers: Vec<Server>,
+
+    /// Probe preferred servers every this many seconds while streaming from a backup one
+    #[clap(long)]
+    pub fail_back: Option<u64>,
 
     ///Client UDP port
     #[clap(short = 'u', long)]
     pub udp_port: Option<u16>,
+
+    /// Receive quotes from the server's multicast groups
+    #[clap(short = 'm', long)]
+    pub multicast: bool,
+
+    /// Receive quotes over the TCP connection instead of UDP
+    #[clap(long, conflicts_with = "multicast")]
+    pub tcp: bool,
+
+    /// Have the server stream to the address it receives the pings from, e.g. behind NAT
+    #[clap(long, conflicts_with_all = ["multicast", "tcp"])]
+    pub nat: bool,
+
+    /// Receive at most one quote per ticker per interval, in milliseconds
+    #[clap(short = 'c', long)]
+    pub conflation_interval: Option<u64>,
+
+    /// Maximum number of quotes per second
+    #[clap(short = 'r', long)]
+    pub max_rate: Option<u32>,
+
+    /// Show the quotes in a live terminal dashboard instead of printing them
+    #[clap(long)]
+    pub tui: bool,
+
+    /// Format of the printed quotes
+    #[clap(long, value_enum, default_value_t = Format::Text, conflicts_with = "tui")]
+    pub format: Format,
+
+    /// Print each quote as this line, with `{ticker}`, `{price}`, `{volume}`, `{timestamp}` and `{kind}` fields
+    #[clap(long, value_parser = parse_template, conflicts_with_all = ["format", "tui"])]
+    pub template: Option<Template>,
+
+    /// Server-side quote filter, e.g. `AAPL:above=150,below=200,change=0.5,volume=100`
+    #[clap(long = "filter", value_parser = parse_filter)]
+    pub filters: Vec<QuoteFilter>,
+
+    /// File of price alert rules, one `TICKER CONDITION THRESHOLD [COOLDOWN]` per line
+    #[clap(long)]
+    pub alerts: Option<std::path::PathBuf>,
+
+    /// Append the alerts to this file instead of printing them to stderr
+    #[clap(long, requires = "alerts")]
+    pub alert_file: Option<std::path::PathBuf>,
+
+    /// Run this shell command for every alert instead of printing it to stderr
+    #[clap(long, requires = "alerts")]
+    pub alert_command: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub(super) enum Format {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line
+    #[value(alias = "jsonl")]
+    Json,
+    /// Comma-separated values with a header line
+    Csv,
 }
 
 #[derive(Debug, Subcommand, Clone)]
-pub(super) enum TickersSource {
+pub(super) enum Command {
     /// File with tickers
     File {
         /// Path to the file with tickers
@@ -29,8 +100,162 @@ pub(super) enum TickersSource {
         tickers_file: std::path::PathBuf,
     },
     /// Tickers provided as command line arguments
-    Args {
-        #[arg(short = 't', long)]
+    Args(Tickers),
+    /// Record the received quotes to a file while printing them
+    Record {
+        /// Path of the recording, replaced if it exists
+        #[arg(short = 'o', long)]
+        out: std::path::PathBuf,
+        /// How the recording is stored
+        #[arg(short = 'e', long, value_enum, default_value_t = Encoding::Binary)]
+        encoding: Encoding,
+        #[command(flatten)]
+        tickers: Tickers,
+    },
+    /// Print a recording, spaced as the quotes were received
+    Replay {
+        /// Path of the recording, binary or JSON Lines
+        #[arg(short = 'i', long)]
+        input: std::path::PathBuf,
+        /// Play back this many times faster than recorded
+        #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+        speed: f64,
+        /// Play back without waiting between the quotes
+        #[arg(long, conflicts_with = "speed")]
+        max_speed: bool,
+    },
+    /// List the ticker groups defined by the server
+    Groups,
+    /// Print the latest known quotes of the tickers and exit
+    Snapshot {
+        /// Tickers or glob patterns, e.g. `NV*`
+        #[arg(short = 't', long, required = true)]
         tickers: Vec<String>,
     },
+    /// Print the journaled quotes of a ticker and exit
+    History {
+        /// Ticker symbol
+        #[arg(short = 't', long)]
+        ticker: String,
+        /// Oldest quote time, RFC 3339, e.g. `2025-01-31T10:00:00Z`, at most a day before `--to` (default: an hour before `--to`)
+        #[arg(long, value_parser = parse_time)]
+        from: Option<i64>,
+        /// Newest quote time, RFC 3339 (default: now)
+        #[arg(long, value_parser = parse_time)]
+        to: Option<i64>,
+        /// Maximum number of quotes, the latest ones are returned
+        #[arg(short = 'n', long, default_value_t = 100)]
+        limit: u32,
+    },
+}
+
+#[derive(Debug, clap::Args, Clone)]
+pub(super) struct Tickers {
+    /// Tickers or glob patterns, e.g. `NV*`
+    #[arg(short = 't', long, required_unless_present_any = ["all", "groups"])]
+    pub tickers: Vec<String>,
+    /// Names of ticker groups defined by the server
+    #[arg(short = 'g', long = "group")]
+    pub groups: Vec<String>,
+    /// Subscribe to every ticker the server publishes
+    #[arg(long, conflicts_with_all = ["tickers", "groups"])]
+    pub all: bool,
+}
+
+fn parse_filter(value: &str) -> Result<QuoteFilter, String> {
+    let (ticker, conditions) = value
+        .split_once(':')
+        .ok_or_else(|| "expected TICKER:condition,...".to_string())?;
+    let mut filter = QuoteFilter::new(ticker.trim());
+
+    for condition in conditions.split(',') {
+        let (name, value) = condition
+            .split_once('=')
+            .ok_or_else(|| format!("expected name=value, got `{}`", condition))?;
+        let value = value.trim();
+        filter = match name.trim() {
+            "above" => filter.with_price_above(parse_decimal(value)?),
+            "below" => filter.with_price_below(parse_decimal(value)?),
+            "change" => filter.with_min_change_percent(parse_decimal(value)?),
+            "volume" => filter.with_min_volume(
+                value
+                    .parse()
+                    .map_err(|e| format!("invalid volume `{}`: {}", value, e))?,
+            ),
+            name => return Err(format!("unknown condition `{}`", name)),
+        };
+    }
+
+    Ok(filter)
+}
+
+fn parse_server(value: &str) -> Result<Server, String> {
+    let (address, priority) = match value.rsplit_once('@') {
+        Some((address, priority)) => (
+            address,
+            priority
+                .parse()
+                .map_err(|e| format!("invalid priority `{}`: {}", priority, e))?,
+        ),
+        None => (value, 0),
+    };
+    let address: SocketAddr = address
+        .parse()
+        .map_err(|e| format!("invalid server address `{}`: {}", address, e))?;
+    Ok(Server::new(address.ip(), address.port(), priority))
+}
+
+fn parse_time(value: &str) -> Result<i64, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.timestamp())
+        .map_err(|e| format!("invalid time `{}`: {}", value, e))
+}
+
+fn parse_template(value: &str) -> Result<Template, String> {
+    value
+        .parse()
+        .map_err(|e| format!("invalid template `{}`: {}", value, e))
+}
+
+fn parse_speed(value: &str) -> Result<f64, String> {
+    match value.parse() {
+        Ok(speed) if speed > 0.0 && f64::is_finite(speed) => Ok(speed),
+        _ => Err(format!("expected a positive factor, got `{}`", value)),
+    }
+}
+
+fn parse_decimal(value: &str) -> Result<Decimal, String> {
+    value
+        .parse()
+        .map_err(|e| format!("invalid number `{}`: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filter() {
+        let filter = parse_filter("AAPL:above=150,below=200.5,change=0.5,volume=100").unwrap();
+        let expected = QuoteFilter::new("AAPL")
+            .with_price_above(Decimal::new(150, 0))
+            .with_price_below(Decimal::new(2005, 1))
+            .with_min_change_percent(Decimal::new(5, 1))
+            .with_min_volume(100);
+        assert_eq!(filter, expected);
+
+        assert!(parse_filter("AAPL").is_err());
+        assert!(parse_filter("AAPL:speed=1").is_err());
+    }
+
+    #[test]
+    fn test_parse_server() {
+        let server = parse_server("10.0.0.2:5152@3").unwrap();
+        assert_eq!(server, Server::new("10.0.0.2".parse().unwrap(), 5152, 3));
+        let server = parse_server("[::1]:5152").unwrap();
+        assert_eq!(server, Server::new("::1".parse().unwrap(), 5152, 0));
+
+        assert!(parse_server("10.0.0.2").is_err());
+        assert!(parse_server("10.0.0.2:5152@high").is_err());
+    }
 }
@@ -0,0 +1,415 @@
+mod server_connect;
+
+use crate::async_subscriber::server_connect::{connect_stream, next_frame, send_request};
+use crate::gap_recovery::{Arrival, GapRecovery};
+use crate::multicast::join_multicast_groups;
+use crate::quote_stream::{Event, MAX_ATTEMPTS, disconnection, is_subscribed, to_event};
+use crate::server_connect::{
+    DEFAULT_READ_TIMEOUT, multicast_groups, session_token, stream_accepted, stream_request,
+};
+use crate::subscriber::{QuoteSubscriber, SubscriberError, Transport};
+use futures_core::Stream;
+use quote_streaming::{Datagram, Delivery, MulticastGroup, Request, StockQuote, encode_frame};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time;
+
+const PING_INTERVAL: Duration = Duration::from_secs(2);
+/// Events buffered for a consumer that falls behind, before receiving pauses.
+const EVENT_CAPACITY: usize = 1024;
+
+type Events = mpsc::Sender<Result<Event, SubscriberError>>;
+
+impl QuoteSubscriber {
+    /// Sends the subscription without blocking a thread and returns the stream of its quotes.
+    ///
+    /// Receiving and pinging run in a task on the current tokio runtime until the stream fails
+    /// or is dropped.
+    pub async fn subscribe_async(&self) -> Result<AsyncQuoteStream, SubscriberError> {
+        let server = self.server;
+        match self.transport {
+            Transport::Unicast => {
+                let socket = bind(self.client_address, self.client_port).await?;
+                let delivery = Delivery::Unicast {
+                    address: self.client_address,
+                    port: self.client_port,
+                };
+                let request =
+                    stream_request(self.subscription.clone(), delivery, self.options.clone());
+                multicast_groups(send_request(request, server).await?)?;
+
+                let ping = serialize(Request::Ping)?;
+                let gap_recovery = GapRecovery::new(server, self.client_address, self.client_port);
+                Ok(AsyncQuoteStream::spawn(Vec::new(), |events| {
+                    stream_udp(socket, server, Some(ping), Some(gap_recovery), None, events)
+                }))
+            }
+            Transport::PingSource => {
+                // The server learns the address from the pings, so any local interface will do
+                let socket = bind(IpAddr::V4(Ipv4Addr::UNSPECIFIED), self.client_port).await?;
+                let request = stream_request(
+                    self.subscription.clone(),
+                    Delivery::PingSource,
+                    self.options.clone(),
+                );
+                let token = session_token(send_request(request, server).await?)?;
+
+                let ping = serialize(Request::SessionPing { token })?;
+                let gap_recovery = GapRecovery::new(server, self.client_address, self.client_port);
+                Ok(AsyncQuoteStream::spawn(Vec::new(), |events| {
+                    stream_udp(socket, server, Some(ping), Some(gap_recovery), None, events)
+                }))
+            }
+            Transport::Multicast => {
+                let request = stream_request(
+                    self.subscription.clone(),
+                    Delivery::Multicast,
+                    self.options.clone(),
+                );
+                let groups = multicast_groups(send_request(request, server).await?)?;
+                let socket = join_multicast_groups(&groups, self.client_address)?;
+                socket
+                    .set_nonblocking(true)
+                    .map_err(SubscriberError::Socket)?;
+                let socket = UdpSocket::from_std(socket).map_err(SubscriberError::Socket)?;
+
+                // Groups carry the tickers the server resolved the subscription patterns to
+                let tickers = groups
+                    .iter()
+                    .flat_map(|group| group.tickers().iter().cloned())
+                    .collect();
+                Ok(AsyncQuoteStream::spawn(groups, |events| {
+                    stream_udp(socket, server, None, None, Some(tickers), events)
+                }))
+            }
+            Transport::Tcp => {
+                let request = stream_request(
+                    self.subscription.clone(),
+                    Delivery::Tcp,
+                    self.options.clone(),
+                );
+                let (stream, answer, received) = connect_stream(request, server).await?;
+                stream_accepted(&answer)?;
+                Ok(AsyncQuoteStream::spawn(Vec::new(), |events| {
+                    stream_tcp(stream, received, events)
+                }))
+            }
+        }
+    }
+}
+
+/// The quotes of a subscription, received by a tokio task until the stream fails.
+///
+/// As a [`Stream`] it yields the quotes only, and ends after the error that failed it;
+/// [`AsyncQuoteStream::next_event`] also returns notices such as throttling and lost
+/// datagrams. Dropping the stream stops the task, and the server ends the subscription once
+/// the pings stop.
+pub struct AsyncQuoteStream {
+    events: mpsc::Receiver<Result<Event, SubscriberError>>,
+    multicast_groups: Vec<MulticastGroup>,
+    task: JoinHandle<()>,
+}
+
+impl AsyncQuoteStream {
+    fn spawn<F>(multicast_groups: Vec<MulticastGroup>, receive: impl FnOnce(Events) -> F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (sender, events) = mpsc::channel(EVENT_CAPACITY);
+        Self {
+            events,
+            multicast_groups,
+            task: tokio::spawn(receive(sender)),
+        }
+    }
+
+    /// Returns the multicast groups the stream receives from, empty for other transports.
+    pub fn multicast_groups(&self) -> &[MulticastGroup] {
+        &self.multicast_groups
+    }
+
+    /// Waits for the next event.
+    ///
+    /// Fails with [`SubscriberError::ServerDisconnected`] when nothing arrives for 5 seconds,
+    /// and keeps failing after the stream has ended.
+    pub async fn next_event(&mut self) -> Result<Event, SubscriberError> {
+        self.events
+            .recv()
+            .await
+            .unwrap_or(Err(SubscriberError::ServerDisconnected))
+    }
+}
+
+impl Stream for AsyncQuoteStream {
+    type Item = Result<StockQuote, SubscriberError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let event = match self.events.poll_recv(cx) {
+                Poll::Ready(event) => event,
+                Poll::Pending => return Poll::Pending,
+            };
+            return match event {
+                Some(Ok(
+                    Event::Quote(quote) | Event::Snapshot(quote) | Event::Retransmitted(quote),
+                )) => Poll::Ready(Some(Ok(quote))),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => Poll::Ready(Some(Err(e))),
+                None => Poll::Ready(None),
+            };
+        }
+    }
+}
+
+impl Drop for AsyncQuoteStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Pings the server on a task of its own, so a consumer that falls behind holds up the
+/// receiving only and the server keeps the session. The task stops when this is dropped.
+struct Pinger(JoinHandle<()>);
+
+impl Pinger {
+    fn udp(socket: Arc<UdpSocket>, server: SocketAddr, ping: Vec<u8>) -> Self {
+        Self(tokio::spawn(async move {
+            let mut pings = time::interval(PING_INTERVAL);
+            loop {
+                pings.tick().await;
+                // A lost ping shows as silence of the server
+                let _ = socket.send_to(&ping, server).await;
+            }
+        }))
+    }
+
+    fn tcp(mut writer: OwnedWriteHalf, ping: Vec<u8>) -> Self {
+        Self(tokio::spawn(async move {
+            let mut pings = time::interval(PING_INTERVAL);
+            loop {
+                pings.tick().await;
+                // The reading notices a closed connection
+                if writer.write_all(&ping).await.is_err() {
+                    return;
+                }
+            }
+        }))
+    }
+}
+
+impl Drop for Pinger {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+async fn stream_udp(
+    socket: UdpSocket,
+    server: SocketAddr,
+    ping: Option<Vec<u8>>,
+    gap_recovery: Option<GapRecovery>,
+    tickers_filter: Option<HashSet<String>>,
+    events: Events,
+) {
+    let socket = Arc::new(socket);
+    let _pinger = ping.map(|ping| Pinger::udp(Arc::clone(&socket), server, ping));
+    let result = receive_udp(&socket, server, gap_recovery, tickers_filter, &events).await;
+    if let Err(e) = result {
+        let _ = events.send(Err(e)).await;
+    }
+}
+
+async fn receive_udp(
+    socket: &UdpSocket,
+    server: SocketAddr,
+    mut gap_recovery: Option<GapRecovery>,
+    tickers_filter: Option<HashSet<String>>,
+    events: &Events,
+) -> Result<(), SubscriberError> {
+    let mut buffer = [0; 1024];
+    let mut invalid = 0;
+
+    loop {
+        // The wait starts after the consumer took the last event, so a slow consumer is not
+        // mistaken for a silent server
+        let len = time::timeout(DEFAULT_READ_TIMEOUT, socket.recv(&mut buffer))
+            .await
+            .map_err(|_| SubscriberError::ServerDisconnected)?
+            .map_err(disconnection)?;
+
+        let datagram = match Datagram::try_from(&buffer[..len]) {
+            Ok(datagram) => datagram,
+            // A stray datagram does not break the stream, a run of them does
+            Err(_) if invalid < MAX_ATTEMPTS => {
+                invalid += 1;
+                continue;
+            }
+            Err(e) => return Err(SubscriberError::InvalidResponse(e)),
+        };
+        invalid = 0;
+
+        let arrival = match gap_recovery.as_mut() {
+            Some(gap_recovery) => gap_recovery.track(datagram.sequence()),
+            None => Arrival::InOrder,
+        };
+        if let (Arrival::Gap { from_seq, to_seq }, Some(gap_recovery)) = (&arrival, &gap_recovery) {
+            let request = gap_recovery.retransmit_request(*from_seq, *to_seq)?;
+            socket
+                .send_to(&request, server)
+                .await
+                .map_err(SubscriberError::Retransmit)?;
+            let gap = Event::Gap {
+                from_seq: *from_seq,
+                to_seq: *to_seq,
+            };
+            if events.send(Ok(gap)).await.is_err() {
+                return Ok(());
+            }
+        }
+        if let Some(event) = to_event(datagram.into_response(), &arrival)
+            && is_subscribed(&event, tickers_filter.as_ref())
+            && events.send(Ok(event)).await.is_err()
+        {
+            return Ok(());
+        }
+    }
+}
+
+async fn stream_tcp(stream: TcpStream, received: Vec<u8>, events: Events) {
+    if let Err(e) = receive_tcp(stream, received, &events).await {
+        let _ = events.send(Err(e)).await;
+    }
+}
+
+async fn receive_tcp(
+    stream: TcpStream,
+    mut buffer: Vec<u8>,
+    events: &Events,
+) -> Result<(), SubscriberError> {
+    let (mut reader, writer) = stream.into_split();
+    let _pinger = Pinger::tcp(writer, encode_frame(&serialize(Request::Ping)?));
+    let mut chunk = [0; 4096];
+
+    loop {
+        while let Some(frame) = next_frame(&mut buffer).map_err(SubscriberError::Io)? {
+            // A corrupted frame means the rest of the stream cannot be trusted either
+            let datagram =
+                Datagram::try_from(frame.as_slice()).map_err(SubscriberError::InvalidResponse)?;
+            if let Some(event) = to_event(datagram.into_response(), &Arrival::InOrder)
+                && events.send(Ok(event)).await.is_err()
+            {
+                return Ok(());
+            }
+        }
+
+        let len = time::timeout(DEFAULT_READ_TIMEOUT, reader.read(&mut chunk))
+            .await
+            .map_err(|_| SubscriberError::ServerDisconnected)?
+            .map_err(disconnection)?;
+        if len == 0 {
+            return Err(SubscriberError::ServerDisconnected);
+        }
+        buffer.extend_from_slice(&chunk[..len]);
+    }
+}
+
+async fn bind(address: IpAddr, port: u16) -> Result<UdpSocket, SubscriberError> {
+    UdpSocket::bind((address, port))
+        .await
+        .map_err(SubscriberError::Socket)
+}
+
+fn serialize(request: Request) -> Result<Vec<u8>, SubscriberError> {
+    request.try_into().map_err(SubscriberError::Serialization)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Subscription;
+    use quote_server::{Server, ServerBuilder};
+
+    fn start_server() -> (Server, crossbeam_channel::Sender<StockQuote>) {
+        let (quote_tx, quote_rx) = crossbeam_channel::unbounded();
+        let server = ServerBuilder::new(vec!["AAPL".to_string()])
+            .with_port(0)
+            .with_workers(1)
+            .with_quote_source(quote_rx)
+            .start()
+            .unwrap();
+        (server, quote_tx)
+    }
+
+    fn subscriber(server: &Server) -> QuoteSubscriber {
+        let subscription = Subscription::new(vec!["AAPL".to_string()], Vec::new());
+        QuoteSubscriber::new(server.local_addr(), subscription)
+    }
+
+    async fn next_quote(stream: &mut AsyncQuoteStream) -> StockQuote {
+        loop {
+            match stream.next_event().await.unwrap() {
+                Event::Quote(quote) => return quote,
+                Event::Pong => {}
+                event => panic!("Unexpected event {:?}", event),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unicast_stream() {
+        let (server, quote_tx) = start_server();
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut stream = subscriber(&server)
+            .with_transport(Transport::Unicast)
+            .with_client_address(IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .with_udp_port(port)
+            .subscribe_async()
+            .await
+            .unwrap();
+
+        let quote = StockQuote::new("AAPL", 18950, 100, 1);
+        quote_tx.send(quote.clone()).unwrap();
+        assert_eq!(next_quote(&mut stream).await, quote);
+
+        drop(stream);
+        server.shutdown().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_stream_outlives_slow_consumer() {
+        let (server, quote_tx) = start_server();
+        let mut stream = subscriber(&server)
+            .with_transport(Transport::Tcp)
+            .subscribe_async()
+            .await
+            .unwrap();
+
+        // Fill the events so the receiving waits, for longer than the server waits for a ping
+        for price in 0..2 * EVENT_CAPACITY as i64 {
+            quote_tx
+                .send(StockQuote::new("AAPL", price, 100, 1))
+                .unwrap();
+        }
+        time::sleep(Duration::from_secs(6)).await;
+
+        let last = StockQuote::new("AAPL", -1, 100, 2);
+        quote_tx.send(last.clone()).unwrap();
+        while next_quote(&mut stream).await != last {}
+
+        drop(stream);
+        server.shutdown().unwrap();
+    }
+}
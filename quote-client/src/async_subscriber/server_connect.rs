@@ -0,0 +1,79 @@
+use crate::server_connect::{DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT, ServerConnectError};
+use quote_streaming::{Request, Response, encode_frame, split_frame};
+use std::io::{Error, ErrorKind};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time;
+
+/// Sends the request on a connection of its own and returns the answer.
+pub(super) async fn send_request(
+    request: Request,
+    server: SocketAddr,
+) -> Result<Response, ServerConnectError> {
+    let bytes: Vec<u8> = request
+        .try_into()
+        .map_err(ServerConnectError::Serialization)?;
+
+    let mut stream = open(server).await?;
+    stream.write_all(&encode_frame(&bytes)).await?;
+
+    // The server closes the connection after the response
+    let mut buffer = Vec::new();
+    within(DEFAULT_READ_TIMEOUT, stream.read_to_end(&mut buffer)).await?;
+
+    Response::try_from(buffer.as_slice()).map_err(ServerConnectError::InvalidResponse)
+}
+
+/// Sends the request of a TCP subscription and returns the connection with the first frame,
+/// the answer, split off the bytes received so far.
+pub(super) async fn connect_stream(
+    request: Request,
+    server: SocketAddr,
+) -> Result<(TcpStream, Vec<u8>, Vec<u8>), ServerConnectError> {
+    let bytes: Vec<u8> = request
+        .try_into()
+        .map_err(ServerConnectError::Serialization)?;
+
+    let mut stream = open(server).await?;
+    stream.set_nodelay(true)?;
+    stream.write_all(&encode_frame(&bytes)).await?;
+
+    // Quotes may follow the answer in the same read
+    let mut buffer = Vec::new();
+    loop {
+        if let Some(frame) = next_frame(&mut buffer)? {
+            return Ok((stream, frame, buffer));
+        }
+        let mut chunk = [0; 4096];
+        let len = within(DEFAULT_READ_TIMEOUT, stream.read(&mut chunk)).await?;
+        if len == 0 {
+            return Err(Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        buffer.extend_from_slice(&chunk[..len]);
+    }
+}
+
+/// Removes the first complete frame from the buffer and returns its payload.
+pub(super) fn next_frame(buffer: &mut Vec<u8>) -> std::io::Result<Option<Vec<u8>>> {
+    let Some((payload, len)) = split_frame(buffer)? else {
+        return Ok(None);
+    };
+    let payload = payload.to_vec();
+    buffer.drain(..len);
+    Ok(Some(payload))
+}
+
+async fn open(server: SocketAddr) -> std::io::Result<TcpStream> {
+    within(DEFAULT_CONNECT_TIMEOUT, TcpStream::connect(server)).await
+}
+
+async fn within<T>(
+    timeout: Duration,
+    operation: impl Future<Output = std::io::Result<T>>,
+) -> std::io::Result<T> {
+    time::timeout(timeout, operation)
+        .await
+        .map_err(|_| Error::from(ErrorKind::TimedOut))?
+}
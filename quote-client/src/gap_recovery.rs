@@ -0,0 +1,122 @@
+use crate::subscriber::SubscriberError;
+use quote_streaming::{Datagram, Request};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+
+/// Position of a received datagram in the session's quote sequence.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Arrival {
+    InOrder,
+    /// Datagrams between the last received one and this one were lost.
+    Gap {
+        from_seq: u64,
+        to_seq: u64,
+    },
+    /// A datagram older than the last received one, usually a retransmission.
+    Late,
+}
+
+/// Tracks the quote sequence of a unicast session and requests lost datagrams again.
+pub(crate) struct GapRecovery {
+    server: SocketAddr,
+    address: IpAddr,
+    port: u16,
+    expected: u64,
+}
+
+impl GapRecovery {
+    pub(crate) fn new(server: SocketAddr, address: IpAddr, port: u16) -> Self {
+        Self {
+            server,
+            address,
+            port,
+            expected: 1,
+        }
+    }
+
+    /// Requests a retransmission over UDP when the datagram reveals a gap.
+    pub(crate) fn receive(
+        &mut self,
+        sequence: u64,
+        socket: &UdpSocket,
+    ) -> Result<Arrival, SubscriberError> {
+        let arrival = self.track(sequence);
+        if let Arrival::Gap { from_seq, to_seq } = arrival {
+            self.request_retransmit(socket, from_seq, to_seq)?;
+        }
+        Ok(arrival)
+    }
+
+    /// Returns where the datagram belongs in the sequence.
+    pub(crate) fn track(&mut self, sequence: u64) -> Arrival {
+        if sequence == Datagram::UNSEQUENCED || sequence == self.expected {
+            self.expected = self.expected.max(sequence + 1);
+            return Arrival::InOrder;
+        }
+        if sequence < self.expected {
+            return Arrival::Late;
+        }
+
+        let gap = Arrival::Gap {
+            from_seq: self.expected,
+            to_seq: sequence - 1,
+        };
+        self.expected = sequence + 1;
+        gap
+    }
+
+    /// Serializes the request to resend the datagrams of a gap.
+    pub(crate) fn retransmit_request(
+        &self,
+        from_seq: u64,
+        to_seq: u64,
+    ) -> Result<Vec<u8>, SubscriberError> {
+        Request::Retransmit {
+            address: self.address,
+            port: self.port,
+            from_seq,
+            to_seq,
+        }
+        .try_into()
+        .map_err(SubscriberError::Serialization)
+    }
+
+    fn request_retransmit(
+        &self,
+        socket: &UdpSocket,
+        from_seq: u64,
+        to_seq: u64,
+    ) -> Result<(), SubscriberError> {
+        let request = self.retransmit_request(from_seq, to_seq)?;
+        socket
+            .send_to(&request, self.server)
+            .map_err(SubscriberError::Retransmit)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track_sequence() {
+        let mut recovery = GapRecovery::new(
+            "127.0.0.1:5152".parse().unwrap(),
+            "127.0.0.1".parse().unwrap(),
+            5153,
+        );
+
+        assert_eq!(recovery.track(1), Arrival::InOrder);
+        assert_eq!(recovery.track(Datagram::UNSEQUENCED), Arrival::InOrder);
+        assert_eq!(recovery.track(2), Arrival::InOrder);
+        assert_eq!(
+            recovery.track(5),
+            Arrival::Gap {
+                from_seq: 3,
+                to_seq: 4
+            }
+        );
+        assert_eq!(recovery.track(3), Arrival::Late);
+        assert_eq!(recovery.track(6), Arrival::InOrder);
+    }
+}
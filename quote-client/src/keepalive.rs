@@ -0,0 +1,76 @@
+use crate::subscriber::SubscriberError;
+use quote_streaming::{Request, write_frame};
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const PING_INTERVAL: Duration = Duration::from_secs(2);
+const SLEEP_STEP: Duration = Duration::from_millis(100);
+
+/// Pings the server from a background thread until dropped.
+///
+/// A ping that cannot be sent stops the thread; the stream then notices the missing pongs.
+pub(crate) struct Keepalive {
+    stopped: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Keepalive {
+    /// Sends the ping request over UDP.
+    pub(crate) fn udp(
+        socket: Arc<UdpSocket>,
+        server: SocketAddr,
+        request: Request,
+    ) -> Result<Self, SubscriberError> {
+        let ping: Vec<u8> = request.try_into().map_err(SubscriberError::Serialization)?;
+        Ok(Self::start(move || {
+            socket.send_to(&ping, server).map(|_| ())
+        }))
+    }
+
+    /// Sends framed pings over the connection of a TCP stream, shared with the other writers
+    /// of frames.
+    pub(crate) fn tcp(stream: Arc<Mutex<TcpStream>>) -> Result<Self, SubscriberError> {
+        let ping: Vec<u8> = Request::Ping
+            .try_into()
+            .map_err(SubscriberError::Serialization)?;
+        Ok(Self::start(move || {
+            let mut stream = stream.lock().unwrap_or_else(PoisonError::into_inner);
+            write_frame(&mut *stream, &ping)
+        }))
+    }
+
+    fn start(mut send: impl FnMut() -> std::io::Result<()> + Send + 'static) -> Self {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stopped = Arc::clone(&stopped);
+            thread::spawn(move || {
+                let mut next_ping = Instant::now();
+                while !stopped.load(Ordering::SeqCst) {
+                    if Instant::now() >= next_ping {
+                        if send().is_err() {
+                            break;
+                        }
+                        next_ping = Instant::now() + PING_INTERVAL;
+                    }
+                    thread::sleep(SLEEP_STEP);
+                }
+            })
+        };
+        Self {
+            stopped,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Keepalive {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
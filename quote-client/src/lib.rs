@@ -0,0 +1,48 @@
+//! Client library for subscribing to a quote server.
+//!
+//! [`QuoteSubscriber`] sends the subscription, keeps it alive with pings, decodes what the
+//! server streams and recovers lost datagrams, so a service can consume quotes without running
+//! the `quote-client` binary:
+//!
+//! ```no_run
+//! use quote_client::{QuoteSubscriber, Subscription};
+//!
+//! let subscription = Subscription::new(vec!["AAPL".to_string()], Vec::new());
+//! let subscriber = QuoteSubscriber::new("127.0.0.1:5152".parse().unwrap(), subscription);
+//! for quote in subscriber.subscribe()? {
+//!     println!("{}", quote?);
+//! }
+//! # Ok::<(), quote_client::SubscriberError>(())
+//! ```
+//!
+//! [`QuoteStream::controller`] returns a [`SubscriptionController`] that adds and removes
+//! tickers, or pauses the stream, while the subscription runs.
+//!
+//! With the `tokio` feature, [`QuoteSubscriber::subscribe_async`] subscribes without blocking
+//! a thread and returns an [`AsyncQuoteStream`] of the quotes.
+
+#![deny(unreachable_pub)]
+#![warn(missing_docs)]
+#[cfg(feature = "tokio")]
+mod async_subscriber;
+mod gap_recovery;
+mod keepalive;
+mod multicast;
+mod quote_stream;
+mod server_connect;
+mod subscriber;
+mod subscription_controller;
+
+/// Stream of quotes of a subscription on a tokio runtime.
+#[cfg(feature = "tokio")]
+pub use async_subscriber::AsyncQuoteStream;
+/// Failures to receive quotes from multicast groups.
+pub use multicast::MulticastError;
+/// Stream of quotes and notices of a subscription.
+pub use quote_stream::{Event, QuoteStream};
+/// Requests answered on a TCP connection of their own.
+pub use server_connect::{ServerConnectError, Subscription, history, list_groups, snapshot};
+/// Subscribing to a quote server.
+pub use subscriber::{QuoteSubscriber, SubscriberError, Transport};
+/// Changes to a running subscription.
+pub use subscription_controller::SubscriptionController;
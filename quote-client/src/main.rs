@@ -3,45 +3,175 @@
 mod app;
 mod args;
 
-use crate::app::App;
-use crate::args::{Args, TickersSource};
+use crate::app::{
+    App, Console, QuoteFormat, Server, load_alerts, replay, show_groups, show_history,
+    show_snapshot,
+};
+use crate::args::{Args, Command, Format, Tickers};
 use clap::Parser;
+use quote_client::{Subscription, Transport};
+use quote_streaming::SubscriptionOptions;
 use std::io::BufRead;
-use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::time::Duration;
 
-const DEFAULT_CLIENT_PORT: u16 = 5153;
-const DEFAULT_CLIENT_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+const ALL_TICKERS: &str = "*";
 
 fn main() {
     let args = Args::parse();
-    let tickers = get_tickers(args.tickers);
-    let client_port = args.udp_port.unwrap_or(DEFAULT_CLIENT_PORT);
-    let server_address = args.server_address;
-    let server_port = args.server_port;
-
-    let app = App::new(
-        server_address,
-        server_port,
-        tickers,
-        DEFAULT_CLIENT_ADDRESS,
-        client_port,
+    // The server given with `-a` and `-p` comes first among servers of the same priority
+    let servers: Vec<Server> = args
+        .server_address
+        .zip(args.server_port)
+        .map(|(address, port)| Server::new(address, port, 0))
+        .into_iter()
+        .chain(args.servers)
+        .collect();
+    let format = match (args.template, args.format) {
+        (Some(template), _) => QuoteFormat::Template(template),
+        (None, Format::Text) => QuoteFormat::Text,
+        (None, Format::Json) => QuoteFormat::JsonLines,
+        (None, Format::Csv) => QuoteFormat::Csv,
+    };
+    // Alerts printed to stderr would draw over the dashboard
+    if args.tui
+        && args.alerts.is_some()
+        && args.alert_file.is_none()
+        && args.alert_command.is_none()
+    {
+        eprintln!("Alerts need --alert-file or --alert-command with --tui");
+        return;
+    }
+    let alerts = match args.alerts.as_deref().map(|rules| {
+        load_alerts(
+            rules,
+            args.alert_file.as_deref(),
+            args.alert_command.clone(),
+        )
+    }) {
+        Some(Ok(alerts)) => Some(alerts),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return;
+        }
+        None => None,
+    };
+    let mut recording = None;
+    let subscription = match args.command {
+        Command::Groups => {
+            if let Err(e) = show_groups(&servers) {
+                eprintln!("{}", e);
+            }
+            return;
+        }
+        Command::Snapshot { tickers } => {
+            if let Err(e) = show_snapshot(&servers, tickers, format) {
+                eprintln!("{}", e);
+            }
+            return;
+        }
+        Command::History {
+            ticker,
+            from,
+            to,
+            limit,
+        } => {
+            if let Err(e) = show_history(&servers, ticker, from, to, limit, format) {
+                eprintln!("{}", e);
+            }
+            return;
+        }
+        Command::Replay {
+            input,
+            speed,
+            max_speed,
+        } => {
+            let speed = (!max_speed).then_some(speed);
+            if let Err(e) = replay(&input, speed, format, alerts) {
+                eprintln!("{}", e);
+            }
+            return;
+        }
+        Command::File { tickers_file } => Subscription::new(read_tickers(tickers_file), Vec::new()),
+        Command::Args(tickers) => subscription(tickers),
+        Command::Record {
+            out,
+            encoding,
+            tickers,
+        } => {
+            recording = Some((out, encoding));
+            subscription(tickers)
+        }
+    };
+    let mut options = SubscriptionOptions::default();
+    if let Some(interval) = args.conflation_interval {
+        options = options.with_conflation(Duration::from_millis(interval));
+    }
+    if let Some(max_rate) = args.max_rate {
+        options = options.with_max_rate(max_rate);
+    }
+    for filter in args.filters {
+        options = options.with_filter(filter);
+    }
+
+    let transport = if args.multicast {
+        Transport::Multicast
+    } else if args.tcp {
+        Transport::Tcp
+    } else if args.nat {
+        Transport::PingSource
+    } else {
+        Transport::Unicast
+    };
+
+    let mut app = App::new(
+        servers,
+        subscription,
+        args.udp_port,
+        transport,
+        options,
+        args.fail_back.map(Duration::from_secs),
     );
-    if let Err(e) = app.run() {
+    if let Some(alerts) = alerts {
+        app = app.with_alerts(alerts);
+    }
+    if let Some((out, encoding)) = recording {
+        app = match app.with_recording(&out, encoding) {
+            Ok(app) => app,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+    }
+    let result = if args.tui {
+        app.run_dashboard()
+    } else {
+        // Machine-readable output starts with the first quote
+        if format.is_text() {
+            app.show_app_title();
+        }
+        app.start_command_console();
+        app.run(&mut Console::new(format))
+    };
+    if let Err(e) = result {
         eprintln!("{}", e);
     }
 }
 
-fn get_tickers(source: TickersSource) -> Vec<String> {
-    match source {
-        TickersSource::File { tickers_file } => {
-            let tickers_file =
-                std::fs::File::open(tickers_file).expect("Failed to open tickers file");
-            let reader = std::io::BufReader::new(tickers_file);
-            reader
-                .lines()
-                .map(|line| line.expect("Cannot read line from tickers file"))
-                .collect()
-        }
-        TickersSource::Args { tickers } => tickers,
+fn subscription(tickers: Tickers) -> Subscription {
+    if tickers.all {
+        Subscription::new(vec![ALL_TICKERS.to_string()], Vec::new())
+    } else {
+        Subscription::new(tickers.tickers, tickers.groups)
     }
 }
+
+fn read_tickers(tickers_file: PathBuf) -> Vec<String> {
+    let tickers_file = std::fs::File::open(tickers_file).expect("Failed to open tickers file");
+    let reader = std::io::BufReader::new(tickers_file);
+    reader
+        .lines()
+        .map(|line| line.expect("Cannot read line from tickers file"))
+        .collect()
+}
@@ -0,0 +1,48 @@
+use quote_streaming::MulticastGroup;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+
+pub(crate) fn join_multicast_groups(
+    groups: &[MulticastGroup],
+    interface: IpAddr,
+) -> Result<UdpSocket, MulticastError> {
+    let IpAddr::V4(interface) = interface else {
+        return Err(MulticastError::UnsupportedAddress(interface));
+    };
+    let Some(port) = groups.first().map(MulticastGroup::port) else {
+        return Err(MulticastError::NoGroups);
+    };
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)).into())?;
+
+    for group in groups {
+        let IpAddr::V4(address) = group.address() else {
+            return Err(MulticastError::UnsupportedAddress(group.address()));
+        };
+        if group.port() != port {
+            return Err(MulticastError::MixedPorts);
+        }
+        socket.join_multicast_v4(&address, &interface)?;
+    }
+
+    Ok(socket.into())
+}
+
+/// Failures to join the multicast groups of a subscription.
+#[derive(Debug, thiserror::Error)]
+pub enum MulticastError {
+    /// None of the subscribed tickers is published to a multicast group.
+    #[error("Server did not return any multicast group for the tickers")]
+    NoGroups,
+    /// The group or interface address is not IPv4.
+    #[error("Only IPv4 multicast is supported, got {0}")]
+    UnsupportedAddress(IpAddr),
+    /// The groups cannot share one socket.
+    #[error("Multicast groups use different ports")]
+    MixedPorts,
+    /// The socket could not join a group.
+    #[error("Failed to join multicast group: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,283 @@
+use crate::gap_recovery::{Arrival, GapRecovery};
+use crate::keepalive::Keepalive;
+use crate::subscriber::SubscriberError;
+use crate::subscription_controller::SubscriptionController;
+use quote_streaming::{Datagram, MulticastGroup, Response, StockQuote, read_frame};
+use std::collections::{HashSet, VecDeque};
+use std::io::ErrorKind;
+use std::net::{TcpStream, UdpSocket};
+use std::sync::Arc;
+
+pub(crate) const MAX_ATTEMPTS: usize = 10;
+
+/// Something the server sent while streaming a subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A new quote.
+    Quote(StockQuote),
+    /// The last known quote of a ticker, sent when the subscription starts.
+    Snapshot(StockQuote),
+    /// A quote sent again after its datagram was lost.
+    Retransmitted(StockQuote),
+    /// Datagrams were lost and their retransmission was requested.
+    Gap {
+        /// The sequence number of the first lost datagram.
+        from_seq: u64,
+        /// The sequence number of the last lost datagram.
+        to_seq: u64,
+    },
+    /// The server delays quotes to stay within the rate limit of the subscription.
+    Throttled {
+        /// The number of quotes per second the server sends at most.
+        max_rate: u32,
+    },
+    /// The server reported an error.
+    ServerError(String),
+    /// The server answered a keepalive ping.
+    Pong,
+}
+
+enum Receiver {
+    Udp {
+        socket: Arc<UdpSocket>,
+        gap_recovery: Option<GapRecovery>,
+        /// Multicast groups carry the quotes of tickers other clients subscribed to as well.
+        tickers_filter: Option<HashSet<String>>,
+        invalid: usize,
+    },
+    Tcp(TcpStream),
+}
+
+/// The quotes of a subscription, received until the stream fails.
+///
+/// As an [`Iterator`] it yields the quotes only; [`QuoteStream::next_event`] also returns
+/// notices such as throttling and lost datagrams. The subscription ends when the stream is
+/// dropped and the server stops hearing its pings.
+pub struct QuoteStream {
+    receiver: Receiver,
+    multicast_groups: Vec<MulticastGroup>,
+    /// Received events with the sequence number of the datagram they came in.
+    pending: VecDeque<(u64, Event)>,
+    sequence: u64,
+    failed: bool,
+    controller: SubscriptionController,
+    _keepalive: Option<Keepalive>,
+}
+
+impl QuoteStream {
+    pub(crate) fn udp(
+        socket: Arc<UdpSocket>,
+        gap_recovery: Option<GapRecovery>,
+        keepalive: Keepalive,
+        controller: SubscriptionController,
+    ) -> Self {
+        Self::new(
+            Receiver::Udp {
+                socket,
+                gap_recovery,
+                tickers_filter: None,
+                invalid: 0,
+            },
+            Vec::new(),
+            Some(keepalive),
+            controller,
+        )
+    }
+
+    pub(crate) fn multicast(
+        socket: UdpSocket,
+        groups: Vec<MulticastGroup>,
+        controller: SubscriptionController,
+    ) -> Self {
+        // Groups carry the tickers the server resolved the subscription patterns to
+        let tickers = groups
+            .iter()
+            .flat_map(|group| group.tickers().iter().cloned())
+            .collect();
+        Self::new(
+            Receiver::Udp {
+                socket: Arc::new(socket),
+                gap_recovery: None,
+                tickers_filter: Some(tickers),
+                invalid: 0,
+            },
+            groups,
+            None,
+            controller,
+        )
+    }
+
+    pub(crate) fn tcp(
+        stream: TcpStream,
+        keepalive: Keepalive,
+        controller: SubscriptionController,
+    ) -> Self {
+        Self::new(
+            Receiver::Tcp(stream),
+            Vec::new(),
+            Some(keepalive),
+            controller,
+        )
+    }
+
+    fn new(
+        receiver: Receiver,
+        multicast_groups: Vec<MulticastGroup>,
+        keepalive: Option<Keepalive>,
+        controller: SubscriptionController,
+    ) -> Self {
+        Self {
+            receiver,
+            multicast_groups,
+            pending: VecDeque::new(),
+            sequence: Datagram::UNSEQUENCED,
+            failed: false,
+            controller,
+            _keepalive: keepalive,
+        }
+    }
+
+    /// Returns the multicast groups the stream receives from, empty for other transports.
+    pub fn multicast_groups(&self) -> &[MulticastGroup] {
+        &self.multicast_groups
+    }
+
+    /// Returns a controller changing the tickers of the subscription while it streams.
+    ///
+    /// Changes of a multicast subscription fail with
+    /// [`SubscriberError::ChangeUnsupported`].
+    pub fn controller(&self) -> SubscriptionController {
+        self.controller.clone()
+    }
+
+    /// Returns the sequence number of the datagram or frame the last event came in.
+    ///
+    /// Quotes of unicast and TCP sessions are numbered from 1; other events, and all
+    /// multicast quotes, are [`Datagram::UNSEQUENCED`]. A [`Event::Gap`] has the number of the
+    /// datagram that revealed it.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Waits for the next event.
+    ///
+    /// Fails with [`SubscriberError::ServerDisconnected`] when nothing arrives for the read
+    /// timeout of the socket, 5 seconds by default.
+    pub fn next_event(&mut self) -> Result<Event, SubscriberError> {
+        loop {
+            if let Some((sequence, event)) = self.pending.pop_front() {
+                self.sequence = sequence;
+                return Ok(event);
+            }
+            self.receive()?;
+        }
+    }
+
+    fn receive(&mut self) -> Result<(), SubscriberError> {
+        match &mut self.receiver {
+            Receiver::Udp {
+                socket,
+                gap_recovery,
+                tickers_filter,
+                invalid,
+            } => {
+                let mut buffer = [0; 1024];
+                let len = socket.recv(&mut buffer).map_err(disconnection)?;
+                let datagram = match Datagram::try_from(&buffer[..len]) {
+                    Ok(datagram) => datagram,
+                    // A stray datagram does not break the stream, a run of them does
+                    Err(_) if *invalid < MAX_ATTEMPTS => {
+                        *invalid += 1;
+                        return Ok(());
+                    }
+                    Err(e) => return Err(SubscriberError::InvalidResponse(e)),
+                };
+                *invalid = 0;
+
+                let arrival = match gap_recovery.as_mut() {
+                    Some(gap_recovery) => gap_recovery.receive(datagram.sequence(), socket)?,
+                    None => Arrival::InOrder,
+                };
+                let sequence = datagram.sequence();
+                if let Arrival::Gap { from_seq, to_seq } = arrival {
+                    self.pending
+                        .push_back((sequence, Event::Gap { from_seq, to_seq }));
+                }
+                if let Some(event) = to_event(datagram.into_response(), &arrival)
+                    && is_subscribed(&event, tickers_filter.as_ref())
+                {
+                    self.pending.push_back((sequence, event));
+                }
+            }
+            Receiver::Tcp(stream) => {
+                let frame = read_frame(stream).map_err(disconnection)?;
+                // A corrupted frame means the rest of the stream cannot be trusted either
+                let datagram = Datagram::try_from(frame.as_slice())
+                    .map_err(SubscriberError::InvalidResponse)?;
+                let sequence = datagram.sequence();
+                if let Some(event) = to_event(datagram.into_response(), &Arrival::InOrder) {
+                    self.pending.push_back((sequence, event));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for QuoteStream {
+    type Item = Result<StockQuote, SubscriberError>;
+
+    /// Returns the next quote, or the error that ended the stream followed by `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            match self.next_event() {
+                Ok(Event::Quote(quote) | Event::Snapshot(quote) | Event::Retransmitted(quote)) => {
+                    return Some(Ok(quote));
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn to_event(response: Response, arrival: &Arrival) -> Option<Event> {
+    match response {
+        Response::Quote(quote) if *arrival == Arrival::Late => Some(Event::Retransmitted(quote)),
+        Response::Quote(quote) => Some(Event::Quote(quote)),
+        Response::SnapshotQuote(quote) => Some(Event::Snapshot(quote)),
+        Response::Throttled { max_rate } => Some(Event::Throttled { max_rate }),
+        Response::Error(err) => Some(Event::ServerError(err)),
+        Response::Pong => Some(Event::Pong),
+        Response::Ok
+        | Response::MulticastGroups(_)
+        | Response::Groups(_)
+        | Response::Quotes(_)
+        | Response::SessionToken(_) => None,
+    }
+}
+
+pub(crate) fn is_subscribed(event: &Event, tickers_filter: Option<&HashSet<String>>) -> bool {
+    let (Event::Quote(quote) | Event::Snapshot(quote) | Event::Retransmitted(quote)) = event else {
+        return true;
+    };
+    tickers_filter.is_none_or(|tickers| tickers.contains(quote.ticker()))
+}
+
+pub(crate) fn disconnection(e: std::io::Error) -> SubscriberError {
+    match e.kind() {
+        ErrorKind::TimedOut
+        | ErrorKind::WouldBlock
+        | ErrorKind::UnexpectedEof
+        | ErrorKind::ConnectionRefused
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted => SubscriberError::ServerDisconnected,
+        _ => SubscriberError::Io(e),
+    }
+}
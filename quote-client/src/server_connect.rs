@@ -0,0 +1,219 @@
+use quote_streaming::{
+    Datagram, Delivery, MulticastGroup, Request, Response, StockQuote, SubscriptionOptions,
+    TickerGroup, read_frame, write_frame,
+};
+use std::io::Read;
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+pub(crate) const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+pub(crate) const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tickers and server-defined groups a client subscribes to.
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    tickers: Vec<String>,
+    groups: Vec<String>,
+}
+
+impl Subscription {
+    /// Creates a subscription to tickers or glob patterns and to ticker groups by name.
+    pub fn new(tickers: Vec<String>, groups: Vec<String>) -> Self {
+        Self { tickers, groups }
+    }
+
+    /// Returns the tickers or glob patterns.
+    pub fn tickers(&self) -> &[String] {
+        &self.tickers
+    }
+
+    /// Returns the names of the server-defined ticker groups.
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+}
+
+pub(crate) fn connect(
+    subscription: Subscription,
+    server_address: IpAddr,
+    server_port: u16,
+    delivery: Delivery,
+    options: SubscriptionOptions,
+) -> Result<Vec<MulticastGroup>, ServerConnectError> {
+    let request = stream_request(subscription, delivery, options);
+    multicast_groups(send_request(request, server_address, server_port)?)
+}
+
+/// Subscribes with ping source delivery and returns the token the pings have to carry.
+pub(crate) fn connect_ping_source(
+    subscription: Subscription,
+    server_address: IpAddr,
+    server_port: u16,
+    options: SubscriptionOptions,
+) -> Result<u64, ServerConnectError> {
+    let request = stream_request(subscription, Delivery::PingSource, options);
+    session_token(send_request(request, server_address, server_port)?)
+}
+
+/// Subscribes with TCP delivery and returns the connection the quotes are streamed over.
+pub(crate) fn connect_stream(
+    subscription: Subscription,
+    server_address: IpAddr,
+    server_port: u16,
+    options: SubscriptionOptions,
+) -> Result<TcpStream, ServerConnectError> {
+    let bytes: Vec<u8> = stream_request(subscription, Delivery::Tcp, options)
+        .try_into()
+        .map_err(ServerConnectError::Serialization)?;
+
+    let mut stream = TcpStream::connect_timeout(
+        &SocketAddr::new(server_address, server_port),
+        DEFAULT_CONNECT_TIMEOUT,
+    )?;
+    stream.set_read_timeout(Some(DEFAULT_READ_TIMEOUT))?;
+    stream.set_nodelay(true)?;
+    write_frame(&mut stream, &bytes)?;
+
+    // From now on the server frames every message, starting with the answer
+    let frame = read_frame(&mut stream)?;
+    stream_accepted(&frame)?;
+    Ok(stream)
+}
+
+pub(crate) fn stream_request(
+    subscription: Subscription,
+    delivery: Delivery,
+    options: SubscriptionOptions,
+) -> Request {
+    Request::StreamTickers {
+        ticker: subscription.tickers,
+        groups: subscription.groups,
+        delivery,
+        options,
+    }
+}
+
+/// Interprets the answer to a unicast or multicast subscription.
+pub(crate) fn multicast_groups(
+    response: Response,
+) -> Result<Vec<MulticastGroup>, ServerConnectError> {
+    match response {
+        Response::Ok => Ok(Vec::new()),
+        Response::MulticastGroups(groups) => Ok(groups),
+        Response::Error(err) => Err(ServerConnectError::SubscriptionFailed(err)),
+        _ => Err(ServerConnectError::UnexpectedResponse),
+    }
+}
+
+/// Interprets the answer to a ping source subscription.
+pub(crate) fn session_token(response: Response) -> Result<u64, ServerConnectError> {
+    match response {
+        Response::SessionToken(token) => Ok(token),
+        Response::Error(err) => Err(ServerConnectError::SubscriptionFailed(err)),
+        _ => Err(ServerConnectError::UnexpectedResponse),
+    }
+}
+
+/// Interprets the first frame of a TCP subscription.
+pub(crate) fn stream_accepted(frame: &[u8]) -> Result<(), ServerConnectError> {
+    let datagram = Datagram::try_from(frame).map_err(ServerConnectError::InvalidResponse)?;
+    match datagram.into_response() {
+        Response::Ok => Ok(()),
+        Response::Error(err) => Err(ServerConnectError::SubscriptionFailed(err)),
+        _ => Err(ServerConnectError::UnexpectedResponse),
+    }
+}
+
+/// Returns the ticker groups defined by the server.
+pub fn list_groups(
+    server_address: IpAddr,
+    server_port: u16,
+) -> Result<Vec<TickerGroup>, ServerConnectError> {
+    match send_request(Request::ListGroups, server_address, server_port)? {
+        Response::Groups(groups) => Ok(groups),
+        Response::Error(err) => Err(ServerConnectError::RequestFailed(err)),
+        _ => Err(ServerConnectError::UnexpectedResponse),
+    }
+}
+
+/// Returns the latest known quote of each ticker.
+pub fn snapshot(
+    tickers: Vec<String>,
+    server_address: IpAddr,
+    server_port: u16,
+) -> Result<Vec<StockQuote>, ServerConnectError> {
+    match send_request(Request::Snapshot { tickers }, server_address, server_port)? {
+        Response::Quotes(quotes) => Ok(quotes),
+        Response::Error(err) => Err(ServerConnectError::RequestFailed(err)),
+        _ => Err(ServerConnectError::UnexpectedResponse),
+    }
+}
+
+/// Returns the latest `limit` journaled quotes of the ticker between the Unix timestamps
+/// `from` and `to`, oldest first.
+pub fn history(
+    ticker: String,
+    from: i64,
+    to: i64,
+    limit: u32,
+    server_address: IpAddr,
+    server_port: u16,
+) -> Result<Vec<StockQuote>, ServerConnectError> {
+    let request = Request::History {
+        ticker,
+        from,
+        to,
+        limit,
+    };
+    match send_request(request, server_address, server_port)? {
+        Response::Quotes(quotes) => Ok(quotes),
+        Response::Error(err) => Err(ServerConnectError::RequestFailed(err)),
+        _ => Err(ServerConnectError::UnexpectedResponse),
+    }
+}
+
+pub(crate) fn send_request(
+    request: Request,
+    server_address: IpAddr,
+    server_port: u16,
+) -> Result<Response, ServerConnectError> {
+    let bytes: Vec<u8> = request
+        .try_into()
+        .map_err(ServerConnectError::Serialization)?;
+
+    let mut stream = TcpStream::connect_timeout(
+        &SocketAddr::new(server_address, server_port),
+        DEFAULT_CONNECT_TIMEOUT,
+    )?;
+    stream.set_read_timeout(Some(DEFAULT_READ_TIMEOUT))?;
+    write_frame(&mut stream, &bytes)?;
+
+    // The server closes the connection after the response
+    let mut buffer = Vec::new();
+    stream.read_to_end(&mut buffer)?;
+
+    Response::try_from(buffer.as_slice()).map_err(ServerConnectError::InvalidResponse)
+}
+
+/// Failures of a request to the server.
+#[derive(Debug, thiserror::Error)]
+pub enum ServerConnectError {
+    /// The request could not be serialized.
+    #[error("Failed to serialize command")]
+    Serialization(rancor::Error),
+    /// The server could not be reached.
+    #[error("Failed to connect to server")]
+    Connection(#[from] std::io::Error),
+    /// The server rejected the subscription.
+    #[error("Failed to subscribe to tickers: {0}")]
+    SubscriptionFailed(String),
+    /// The server rejected the request.
+    #[error("Server rejected request: {0}")]
+    RequestFailed(String),
+    /// The response could not be deserialized.
+    #[error("Server returned invalid response")]
+    InvalidResponse(rancor::Error),
+    /// The response does not answer the request.
+    #[error("Server returned unexpected response")]
+    UnexpectedResponse,
+}
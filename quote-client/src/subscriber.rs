@@ -0,0 +1,247 @@
+use crate::gap_recovery::GapRecovery;
+use crate::keepalive::Keepalive;
+use crate::multicast::{MulticastError, join_multicast_groups};
+use crate::quote_stream::QuoteStream;
+use crate::server_connect::{
+    ServerConnectError, Subscription, connect, connect_ping_source, connect_stream,
+};
+use crate::subscription_controller::SubscriptionController;
+use quote_streaming::{Delivery, Request, StockQuote, SubscriptionOptions};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const DEFAULT_CLIENT_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const DEFAULT_CLIENT_PORT: u16 = 5153;
+const DEFAULT_SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How the server delivers the streamed quotes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Transport {
+    /// Unicast UDP datagrams, kept alive with UDP pings.
+    #[default]
+    Unicast,
+    /// Unicast UDP datagrams sent to wherever the server sees the client's pings come from.
+    PingSource,
+    /// The server's multicast groups.
+    Multicast,
+    /// Frames on the TCP connection of the subscription, kept alive with framed pings.
+    Tcp,
+}
+
+/// Subscribes to the quotes of one server.
+///
+/// Every call to [`subscribe`](Self::subscribe) starts a new subscription, so a subscriber can
+/// be reused to subscribe again after its stream failed.
+#[derive(Debug, Clone)]
+pub struct QuoteSubscriber {
+    pub(crate) server: SocketAddr,
+    pub(crate) subscription: Subscription,
+    pub(crate) options: SubscriptionOptions,
+    pub(crate) transport: Transport,
+    pub(crate) client_address: IpAddr,
+    pub(crate) client_port: u16,
+}
+
+impl QuoteSubscriber {
+    /// Creates a subscriber receiving unicast UDP on `127.0.0.1:5153`.
+    pub fn new(server: SocketAddr, subscription: Subscription) -> Self {
+        Self {
+            server,
+            subscription,
+            options: SubscriptionOptions::default(),
+            transport: Transport::default(),
+            client_address: DEFAULT_CLIENT_ADDRESS,
+            client_port: DEFAULT_CLIENT_PORT,
+        }
+    }
+
+    /// Sets the conflation, rate limit and filters the server applies to the subscription.
+    pub fn with_options(mut self, options: SubscriptionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets how the server delivers the quotes.
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets the address the server sends unicast datagrams to, and the interface multicast
+    /// groups are joined on.
+    pub fn with_client_address(mut self, address: IpAddr) -> Self {
+        self.client_address = address;
+        self
+    }
+
+    /// Sets the local port receiving unicast datagrams.
+    pub fn with_udp_port(mut self, port: u16) -> Self {
+        self.client_port = port;
+        self
+    }
+
+    /// Sends the subscription and returns the stream of its quotes.
+    pub fn subscribe(&self) -> Result<QuoteStream, SubscriberError> {
+        match self.transport {
+            Transport::Unicast => {
+                let socket = self.create_udp_socket(self.client_address)?;
+                connect(
+                    self.subscription.clone(),
+                    self.server.ip(),
+                    self.server.port(),
+                    Delivery::Unicast {
+                        address: self.client_address,
+                        port: self.client_port,
+                    },
+                    self.options.clone(),
+                )?;
+                let keepalive = Keepalive::udp(Arc::clone(&socket), self.server, Request::Ping)?;
+                let gap_recovery =
+                    GapRecovery::new(self.server, self.client_address, self.client_port);
+                let controller = SubscriptionController::request(
+                    self.server,
+                    self.client_address,
+                    self.client_port,
+                );
+                Ok(QuoteStream::udp(
+                    socket,
+                    Some(gap_recovery),
+                    keepalive,
+                    controller,
+                ))
+            }
+            Transport::PingSource => {
+                // The server learns the address from the pings, so any local interface will do
+                let socket = self.create_udp_socket(IpAddr::V4(Ipv4Addr::UNSPECIFIED))?;
+                let token = connect_ping_source(
+                    self.subscription.clone(),
+                    self.server.ip(),
+                    self.server.port(),
+                    self.options.clone(),
+                )?;
+                let keepalive = Keepalive::udp(
+                    Arc::clone(&socket),
+                    self.server,
+                    Request::SessionPing { token },
+                )?;
+                let gap_recovery =
+                    GapRecovery::new(self.server, self.client_address, self.client_port);
+                // Routed by the source of the datagram, like the retransmit requests
+                let controller = SubscriptionController::datagram(
+                    Arc::clone(&socket),
+                    self.server,
+                    self.client_address,
+                    self.client_port,
+                );
+                Ok(QuoteStream::udp(
+                    socket,
+                    Some(gap_recovery),
+                    keepalive,
+                    controller,
+                ))
+            }
+            Transport::Multicast => {
+                let groups = connect(
+                    self.subscription.clone(),
+                    self.server.ip(),
+                    self.server.port(),
+                    Delivery::Multicast,
+                    self.options.clone(),
+                )?;
+                let socket = join_multicast_groups(&groups, self.client_address)?;
+                socket
+                    .set_read_timeout(Some(DEFAULT_SOCKET_TIMEOUT))
+                    .map_err(SubscriberError::Socket)?;
+                let controller =
+                    SubscriptionController::unsupported(self.client_address, self.client_port);
+                Ok(QuoteStream::multicast(socket, groups, controller))
+            }
+            Transport::Tcp => {
+                let stream = connect_stream(
+                    self.subscription.clone(),
+                    self.server.ip(),
+                    self.server.port(),
+                    self.options.clone(),
+                )?;
+                let writer = Arc::new(Mutex::new(
+                    stream.try_clone().map_err(SubscriberError::Socket)?,
+                ));
+                let keepalive = Keepalive::tcp(Arc::clone(&writer))?;
+                let controller =
+                    SubscriptionController::frame(writer, self.client_address, self.client_port);
+                Ok(QuoteStream::tcp(stream, keepalive, controller))
+            }
+        }
+    }
+
+    /// Subscribes and calls `callback` with every quote until the stream fails.
+    pub fn for_each_quote(
+        &self,
+        mut callback: impl FnMut(StockQuote),
+    ) -> Result<(), SubscriberError> {
+        for quote in self.subscribe()? {
+            callback(quote?);
+        }
+        Ok(())
+    }
+
+    fn create_udp_socket(&self, address: IpAddr) -> Result<Arc<UdpSocket>, SubscriberError> {
+        let socket =
+            UdpSocket::bind((address, self.client_port)).map_err(SubscriberError::Socket)?;
+        socket
+            .set_read_timeout(Some(DEFAULT_SOCKET_TIMEOUT))
+            .map_err(SubscriberError::Socket)?;
+        socket
+            .set_write_timeout(Some(DEFAULT_SOCKET_TIMEOUT))
+            .map_err(SubscriberError::Socket)?;
+        Ok(Arc::new(socket))
+    }
+}
+
+/// Failures to subscribe or to receive the quotes of a subscription.
+#[derive(Debug, thiserror::Error)]
+pub enum SubscriberError {
+    /// The subscription request failed.
+    #[error("{0}")]
+    Connect(#[from] ServerConnectError),
+    /// The socket receiving the quotes could not be set up.
+    #[error("Failed to set up socket: {0}")]
+    Socket(std::io::Error),
+    /// The multicast groups could not be joined.
+    #[error("Failed to receive multicast quotes: {0}")]
+    Multicast(#[from] MulticastError),
+    /// Nothing arrived from the server for the read timeout, or it closed the connection.
+    #[error("Server disconnected")]
+    ServerDisconnected,
+    /// Receiving from the socket failed.
+    #[error("Failed to read response: {0}")]
+    Io(std::io::Error),
+    /// The server sent data that could not be deserialized.
+    #[error("Failed to deserialize response: {0}")]
+    InvalidResponse(rancor::Error),
+    /// A ping or retransmission request could not be serialized.
+    #[error("Failed to serialize request: {0}")]
+    Serialization(rancor::Error),
+    /// The retransmission of lost datagrams could not be requested.
+    #[error("Failed to request retransmission: {0}")]
+    Retransmit(std::io::Error),
+    /// A change of the subscription could not be sent.
+    #[error("Failed to send subscription change: {0}")]
+    Change(std::io::Error),
+    /// The subscription is delivered over multicast groups, which cannot be changed.
+    #[error("Multicast subscriptions cannot be changed")]
+    ChangeUnsupported,
+}
+
+impl SubscriberError {
+    /// Returns `true` when the server went away and subscribing again may succeed.
+    pub fn is_disconnection(&self) -> bool {
+        match self {
+            SubscriberError::Connect(ServerConnectError::Connection(_))
+            | SubscriberError::ServerDisconnected => true,
+            SubscriberError::Retransmit(e) => e.kind() == std::io::ErrorKind::ConnectionRefused,
+            _ => false,
+        }
+    }
+}
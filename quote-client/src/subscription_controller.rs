@@ -0,0 +1,127 @@
+use crate::server_connect::{ServerConnectError, send_request};
+use crate::subscriber::SubscriberError;
+use quote_streaming::{Request, Response, SubscriptionChange, write_frame};
+use std::net::{IpAddr, SocketAddr, TcpStream, UdpSocket};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Where the changes of a subscription are sent.
+#[derive(Debug, Clone)]
+enum Route {
+    /// A TCP request of its own, answered by the server.
+    Request { server: SocketAddr },
+    /// A datagram from the socket receiving the quotes, routed like a retransmit request.
+    Datagram {
+        socket: Arc<UdpSocket>,
+        server: SocketAddr,
+    },
+    /// A frame on the connection the quotes are streamed over.
+    Frame(Arc<Mutex<TcpStream>>),
+    /// Multicast groups are shared with other clients and cannot be changed.
+    Unsupported,
+}
+
+/// Changes what the server streams to a running subscription, without subscribing again.
+///
+/// Returned by [`QuoteStream::controller`](crate::QuoteStream::controller). The controller
+/// can be cloned and used from another thread while the stream is read. Only unicast
+/// subscriptions learn whether the server applied a change; with other transports the change
+/// is sent along with the pings and its effect shows in the stream.
+#[derive(Debug, Clone)]
+pub struct SubscriptionController {
+    route: Route,
+    address: IpAddr,
+    port: u16,
+}
+
+impl SubscriptionController {
+    pub(crate) fn request(server: SocketAddr, address: IpAddr, port: u16) -> Self {
+        Self {
+            route: Route::Request { server },
+            address,
+            port,
+        }
+    }
+
+    pub(crate) fn datagram(
+        socket: Arc<UdpSocket>,
+        server: SocketAddr,
+        address: IpAddr,
+        port: u16,
+    ) -> Self {
+        Self {
+            route: Route::Datagram { socket, server },
+            address,
+            port,
+        }
+    }
+
+    pub(crate) fn frame(stream: Arc<Mutex<TcpStream>>, address: IpAddr, port: u16) -> Self {
+        Self {
+            route: Route::Frame(stream),
+            address,
+            port,
+        }
+    }
+
+    pub(crate) fn unsupported(address: IpAddr, port: u16) -> Self {
+        Self {
+            route: Route::Unsupported,
+            address,
+            port,
+        }
+    }
+
+    /// Streams these tickers or glob patterns as well, starting with a snapshot of each.
+    pub fn add(&self, tickers: Vec<String>) -> Result<(), SubscriberError> {
+        self.change(SubscriptionChange::Add(tickers))
+    }
+
+    /// Stops streaming these tickers, or the tickers matching these glob patterns.
+    pub fn remove(&self, tickers: Vec<String>) -> Result<(), SubscriberError> {
+        self.change(SubscriptionChange::Remove(tickers))
+    }
+
+    /// Stops the quotes while keeping the subscription alive.
+    pub fn pause(&self) -> Result<(), SubscriberError> {
+        self.change(SubscriptionChange::Pause)
+    }
+
+    /// Sends quotes again, starting with a snapshot of every subscribed ticker.
+    pub fn resume(&self) -> Result<(), SubscriberError> {
+        self.change(SubscriptionChange::Resume)
+    }
+
+    /// Sends the change to the session of the subscription.
+    pub fn change(&self, change: SubscriptionChange) -> Result<(), SubscriberError> {
+        let request = Request::ChangeSubscription {
+            address: self.address,
+            port: self.port,
+            change,
+        };
+        match &self.route {
+            Route::Request { server } => match send_request(request, server.ip(), server.port())? {
+                Response::Ok => Ok(()),
+                Response::Error(err) => Err(ServerConnectError::RequestFailed(err).into()),
+                _ => Err(ServerConnectError::UnexpectedResponse.into()),
+            },
+            Route::Datagram { socket, server } => {
+                let bytes = serialize(request)?;
+                socket
+                    .send_to(&bytes, server)
+                    .map_err(SubscriberError::Change)?;
+                Ok(())
+            }
+            Route::Frame(stream) => {
+                let bytes = serialize(request)?;
+                // The keepalive writes its pings to the same connection
+                let mut stream = stream.lock().unwrap_or_else(PoisonError::into_inner);
+                write_frame(&mut *stream, &bytes).map_err(SubscriberError::Change)
+            }
+            Route::Unsupported => Err(SubscriberError::ChangeUnsupported),
+        }
+    }
+}
+
+fn serialize(request: Request) -> Result<Vec<u8>, SubscriberError> {
+    request.try_into().map_err(SubscriberError::Serialization)
+}
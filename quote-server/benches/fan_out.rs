@@ -0,0 +1,55 @@
+//! Fans a quote out to 1,000 sessions subscribed to its ticker.
+//!
+//! `tickers_router` routes it through the tickers router of the server, which serializes the
+//! quote once and shares the bytes, and takes it from the session queues as the stream workers
+//! do. `serialize_per_subscriber` is the baseline: every subscriber gets a clone of the
+//! `StockQuote` and serializes it itself.
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use crossbeam_channel::{Receiver, Sender};
+use quote_server::FanOut;
+use quote_streaming::{Response, StockQuote};
+use std::hint::black_box;
+
+const SUBSCRIBERS: u32 = 1_000;
+const QUEUE_CAPACITY: usize = 16;
+
+fn serialize_per_subscriber(c: &mut Criterion, quote: &StockQuote) {
+    let channels: Vec<(Sender<StockQuote>, Receiver<StockQuote>)> = (0..SUBSCRIBERS)
+        .map(|_| crossbeam_channel::bounded(QUEUE_CAPACITY))
+        .collect();
+    c.benchmark_group("fan_out")
+        .throughput(Throughput::Elements(u64::from(SUBSCRIBERS)))
+        .bench_function("serialize_per_subscriber", |b| {
+            b.iter(|| {
+                for (tx, _) in channels.iter() {
+                    tx.send(quote.clone()).unwrap();
+                }
+                for (_, rx) in channels.iter() {
+                    let bytes: Vec<u8> = Response::Quote(rx.recv().unwrap()).try_into().unwrap();
+                    black_box(bytes);
+                }
+            })
+        });
+}
+
+fn tickers_router(c: &mut Criterion, quote: &StockQuote) {
+    let fan_out = FanOut::new("AAPL", SUBSCRIBERS, QUEUE_CAPACITY).unwrap();
+    c.benchmark_group("fan_out")
+        .throughput(Throughput::Elements(u64::from(SUBSCRIBERS)))
+        .bench_function("tickers_router", |b| {
+            b.iter(|| {
+                fan_out.send_quote(quote.clone()).unwrap();
+                black_box(fan_out.drain());
+            })
+        });
+}
+
+fn fan_out(c: &mut Criterion) {
+    let quote = StockQuote::generate("AAPL");
+    serialize_per_subscriber(c, &quote);
+    tickers_router(c, &quote);
+}
+
+criterion_group!(benches, fan_out);
+criterion_main!(benches);
@@ -1,146 +1,297 @@
 mod client_address;
 mod error;
+mod journal;
 mod listener;
 mod monitoring;
 mod quote_streaming;
+mod server;
 mod server_cancellation_token;
 
-use crate::app::client_address::ClientAddress;
-use crate::app::error::AppError;
-use crate::app::listener::{ListenContext, run_listening};
+use crate::app::journal::{HistoryQuery, Journal, run_history_reader, run_journal_writer};
+use crate::app::listener::{
+    ConnectionHandlerContext, ListenContext, PendingConnection, run_connection_handlers,
+    run_listening,
+};
 use crate::app::monitoring::{MonitoringRouter, run_monitoring};
-use crate::app::quote_streaming::{TickersRouter, run_quotes_generator};
+use crate::app::quote_streaming::{
+    MulticastGroups, OverflowCounters, SessionQueue, StreamQuotesContext, StreamQuotesPool,
+    StreamingConfig, TickersRouter, run_multicast_publisher, run_quote_source,
+    run_quotes_generator, run_stream_quotes, run_tickers_reloader,
+};
 use crate::app::server_cancellation_token::ServerCancellationToken;
-use crossbeam_channel::Receiver;
-use std::collections::HashSet;
+use ::quote_streaming::{StockQuote, TickerGroup};
+use std::io::ErrorKind;
 use std::mem;
-use std::net::{IpAddr, TcpListener, UdpSocket};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, UdpSocket};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::thread::JoinHandle;
-use std::time::Duration;
-use tracing::{error, info, trace};
+use std::thread::{self, JoinHandle, available_parallelism};
+use std::time::{Duration, Instant};
+use tracing::warn;
 
+pub use crate::app::error::ServerError;
+pub use crate::app::journal::{JournalConfig, JournalError};
+#[cfg(feature = "bench")]
+pub use crate::app::quote_streaming::FanOut;
+pub use crate::app::quote_streaming::{
+    MulticastConfig, MulticastError, OverflowPolicy, TickersRouterError, read_ticker_groups,
+    read_tickers,
+};
+pub use crate::app::server::{Server, ShutdownHandle};
+
+const DEFAULT_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const DEFAULT_PORT: u16 = 5152;
+const DEFAULT_QUEUE_CAPACITY: usize = 1024;
 const STOP_DURATION: Duration = Duration::from_millis(100);
+const DEFAULT_CONNECTION_HANDLERS: usize = 4;
+const HISTORY_QUEUE_CAPACITY: usize = 16;
+const OVERFLOW_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// Attempts to find a port free for both TCP and UDP when binding to port 0.
+const BIND_ATTEMPTS: usize = 16;
 
-pub(super) struct App {
-    service_threads: Vec<JoinHandle<()>>,
-    client_threads: Vec<JoinHandle<Option<ClientAddress>>>,
-    cancellation_token: Arc<ServerCancellationToken>,
+/// Configures and starts a quote server.
+#[derive(Debug, Clone)]
+pub struct ServerBuilder {
     address: IpAddr,
     port: u16,
     tickers: Vec<String>,
+    tickers_file: Option<PathBuf>,
+    groups: Vec<TickerGroup>,
+    multicast: Option<MulticastConfig>,
+    journal: Option<JournalConfig>,
+    workers: Option<usize>,
+    connection_handlers: usize,
+    queue_capacity: usize,
+    overflow_policy: OverflowPolicy,
+    max_rate: Option<u32>,
+    quote_source: Option<crossbeam_channel::Receiver<StockQuote>>,
 }
 
-impl App {
-    pub(super) fn new(address: IpAddr, port: u16, tickers: Vec<String>) -> Self {
+impl ServerBuilder {
+    /// Creates a server generating quotes for the tickers, listening on `127.0.0.1:5152`.
+    pub fn new(tickers: Vec<String>) -> Self {
         Self {
-            service_threads: Vec::new(),
-            client_threads: Vec::new(),
-            cancellation_token: Arc::new(ServerCancellationToken::default()),
-            address,
-            port,
+            address: DEFAULT_ADDRESS,
+            port: DEFAULT_PORT,
             tickers,
+            tickers_file: None,
+            groups: Vec::new(),
+            multicast: None,
+            journal: None,
+            workers: None,
+            connection_handlers: DEFAULT_CONNECTION_HANDLERS,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow_policy: OverflowPolicy::DropOldest,
+            max_rate: None,
+            quote_source: None,
         }
     }
 
-    pub(super) fn run(self) {
-        if let Err(err) = self.run_app() {
-            error!("{}", err);
-        }
+    /// Sets the address the TCP listener and the UDP socket bind to.
+    pub fn with_address(mut self, address: IpAddr) -> Self {
+        self.address = address;
+        self
     }
 
-    fn run_app(mut self) -> Result<(), AppError> {
-        let tcp_listener = TcpListener::bind((self.address, self.port))?;
-        tcp_listener.set_nonblocking(true)?;
-
-        let udp_socket = Arc::new(UdpSocket::bind((self.address, self.port))?);
-        udp_socket.set_nonblocking(true)?;
-        udp_socket.set_read_timeout(Some(Duration::from_millis(500)))?;
-
-        let tickers_router = Arc::new(TickersRouter::new());
-        let monitoring_router = Arc::new(MonitoringRouter::default());
+    /// Sets the port of the TCP listener and the UDP socket; 0 lets the system choose one,
+    /// see [`Server::local_addr`].
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
 
-        set_ctrlc_handler(Arc::clone(&self.cancellation_token))?;
+    /// Watches the file the tickers were read from and reloads them when it changes.
+    pub fn with_tickers_file(mut self, tickers_file: PathBuf) -> Self {
+        self.tickers_file = Some(tickers_file);
+        self
+    }
 
-        self.run_monitoring(Arc::clone(&udp_socket), Arc::clone(&monitoring_router));
-        self.run_quotes_generator(Arc::clone(&tickers_router));
-        let thread_rx = self.run_listening(
-            tcp_listener,
-            Arc::clone(&udp_socket),
-            Arc::clone(&tickers_router),
-            Arc::clone(&monitoring_router),
-        );
+    /// Defines the ticker groups clients may subscribe to by name.
+    pub fn with_groups(mut self, groups: Vec<TickerGroup>) -> Self {
+        self.groups = groups;
+        self
+    }
 
-        self.check_treads(thread_rx, monitoring_router, tickers_router)?;
+    /// Publishes every quote to multicast groups as well.
+    pub fn with_multicast(mut self, multicast: MulticastConfig) -> Self {
+        self.multicast = Some(multicast);
+        self
+    }
 
-        info!("Server stopped");
+    /// Journals the quotes, enabling history queries.
+    pub fn with_journal(mut self, journal: JournalConfig) -> Self {
+        self.journal = Some(journal);
+        self
+    }
 
-        Ok(())
+    /// Sets the number of threads streaming quotes to the sessions, by default one per CPU.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = Some(workers).filter(|workers| *workers > 0);
+        self
     }
 
-    fn check_treads(
-        mut self,
-        client_thread_rx: Receiver<JoinHandle<Option<ClientAddress>>>,
-        monitoring_router: Arc<MonitoringRouter>,
-        tickers_router: Arc<TickersRouter>,
-    ) -> Result<(), AppError> {
-        while !self.cancellation_token.is_cancelled() {
-            match client_thread_rx.try_recv() {
-                Ok(thread) => self.client_threads.push(thread),
-                Err(crossbeam_channel::TryRecvError::Empty) => std::thread::sleep(STOP_DURATION),
-                Err(crossbeam_channel::TryRecvError::Disconnected) => {
-                    self.cancellation_token.cancel();
-                    break;
-                }
-            }
-            self.check_clients_threads(&monitoring_router, &tickers_router)?;
+    /// Sets the number of threads reading and answering the requests, 4 by default.
+    pub fn with_connection_handlers(mut self, connection_handlers: usize) -> Self {
+        if connection_handlers > 0 {
+            self.connection_handlers = connection_handlers;
         }
+        self
+    }
 
-        self.join_threads()?;
+    /// Sets the maximum number of quotes queued for a session, 1024 by default.
+    pub fn with_queue_capacity(mut self, queue_capacity: usize) -> Self {
+        if queue_capacity > 0 {
+            self.queue_capacity = queue_capacity;
+        }
+        self
+    }
 
-        Ok(())
+    /// Sets what happens to quotes for a session whose queue is full.
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
     }
 
-    fn check_clients_threads(
-        &mut self,
-        monitoring_router: &Arc<MonitoringRouter>,
-        tickers_router: &Arc<TickersRouter>,
-    ) -> Result<(), AppError> {
-        let mut threads_to_delete = vec![];
-
-        for (i, thread) in self.client_threads.iter().enumerate() {
-            if thread.is_finished() {
-                threads_to_delete.push(i);
-            }
-        }
+    /// Limits the number of quotes per second sent to a session.
+    pub fn with_max_rate(mut self, max_rate: u32) -> Self {
+        self.max_rate = Some(max_rate).filter(|rate| *rate > 0);
+        self
+    }
 
-        for i in threads_to_delete.into_iter().rev() {
-            let thread = self.client_threads.swap_remove(i);
-            trace!("Joining thread");
-            let client_address = thread.join().map_err(|_| AppError::ThreadJoin)?;
-            if let Some(address) = client_address {
-                monitoring_router.delete(&address)?;
-                tickers_router.delete_clients(HashSet::from([address]))?;
-            }
-        }
+    /// Streams the quotes received from the channel instead of generating them. The tickers
+    /// given to [`ServerBuilder::new`] remain the ones clients subscribe to, and the server
+    /// stops routing quotes once every sender is dropped.
+    pub fn with_quote_source(mut self, quote_rx: crossbeam_channel::Receiver<StockQuote>) -> Self {
+        self.quote_source = Some(quote_rx);
+        self
+    }
 
-        Ok(())
+    /// Binds the sockets and starts the server threads.
+    pub fn start(self) -> Result<Server, ServerError> {
+        App::new(self).start()
     }
+}
 
-    fn join_threads(self) -> Result<(), AppError> {
-        for (i, thread) in self.client_threads.into_iter().enumerate() {
-            trace!("Waiting for client thread {} to finish", i);
-            thread.join().map_err(|_| AppError::ThreadJoin)?;
+/// Starts the services of a server and collects their threads.
+struct App {
+    service_threads: Vec<JoinHandle<()>>,
+    cancellation_token: Arc<ServerCancellationToken>,
+    config: ServerBuilder,
+    streaming_config: StreamingConfig,
+    session_queue: SessionQueue,
+    overflow_counters: Arc<OverflowCounters>,
+}
+
+impl App {
+    fn new(config: ServerBuilder) -> Self {
+        let workers = config
+            .workers
+            .unwrap_or_else(|| available_parallelism().map_or(1, NonZeroUsize::get));
+        let streaming_config = StreamingConfig::new(
+            workers,
+            config.queue_capacity,
+            config.overflow_policy,
+            config.max_rate,
+        );
+        let overflow_counters = Arc::new(OverflowCounters::default());
+        Self {
+            service_threads: Vec::new(),
+            cancellation_token: Arc::new(ServerCancellationToken::default()),
+            session_queue: SessionQueue::new(
+                streaming_config.queue_capacity(),
+                streaming_config.overflow_policy(),
+                Arc::clone(&overflow_counters),
+            ),
+            config,
+            streaming_config,
+            overflow_counters,
         }
+    }
 
-        for (i, thread) in self.service_threads.into_iter().enumerate() {
-            trace!("Waiting for service thread {} to finish", i);
-            thread.join().map_err(|_| AppError::ThreadJoin)?;
+    fn start(mut self) -> Result<Server, ServerError> {
+        let (tcp_listener, udp_socket) = bind(self.config.address, self.config.port)?;
+        let local_addr = tcp_listener.local_addr()?;
+
+        // Reads block for the timeout, so the monitoring sees a cancellation without spinning
+        let udp_socket = Arc::new(udp_socket);
+        udp_socket.set_read_timeout(Some(Duration::from_millis(500)))?;
+
+        let tickers_router = Arc::new(TickersRouter::new(
+            self.config.tickers.clone(),
+            mem::take(&mut self.config.groups),
+        ));
+        let monitoring_router = Arc::new(MonitoringRouter::default());
+
+        // Stop the services that did start before reporting the failure
+        let result = self.run_services(tcp_listener, udp_socket, tickers_router, monitoring_router);
+        if let Err(err) = result {
+            self.cancellation_token.cancel();
+            for thread in self.service_threads {
+                let _ = thread.join();
+            }
+            return Err(err);
         }
 
+        Ok(Server::new(
+            local_addr,
+            self.cancellation_token,
+            self.service_threads,
+        ))
+    }
+
+    fn run_services(
+        &mut self,
+        tcp_listener: TcpListener,
+        udp_socket: Arc<UdpSocket>,
+        tickers_router: Arc<TickersRouter>,
+        monitoring_router: Arc<MonitoringRouter>,
+    ) -> Result<(), ServerError> {
+        let multicast_groups = self.run_multicast_publisher(&tickers_router)?;
+        let history_tx = self.run_journal(&tickers_router)?;
+        self.run_monitoring(Arc::clone(&udp_socket), Arc::clone(&monitoring_router));
+        self.run_quotes_generator(Arc::clone(&tickers_router));
+        self.run_tickers_reloader(Arc::clone(&tickers_router), multicast_groups.clone());
+        self.run_overflow_reporter();
+        let stream_quotes_pool = self.run_stream_quotes(
+            udp_socket,
+            Arc::clone(&tickers_router),
+            Arc::clone(&monitoring_router),
+        );
+        let connection_tx = self.run_connection_handlers(
+            ConnectionHandlerContext::new(
+                Arc::clone(&self.cancellation_token),
+                tickers_router,
+                monitoring_router,
+                multicast_groups,
+                stream_quotes_pool,
+                self.session_queue.clone(),
+                self.streaming_config.clone(),
+            )
+            .with_history(history_tx),
+        );
+        self.run_listening(tcp_listener, connection_tx);
         Ok(())
     }
 
+    fn run_overflow_reporter(&mut self) {
+        let cancellation_token = Arc::clone(&self.cancellation_token);
+        let overflow_counters = Arc::clone(&self.overflow_counters);
+        let reporter_thread = thread::spawn(move || {
+            let mut last_report = Instant::now();
+            let mut reported = (0, 0, 0);
+            while !cancellation_token.is_cancelled() {
+                thread::sleep(STOP_DURATION);
+                if last_report.elapsed() >= OVERFLOW_REPORT_INTERVAL {
+                    last_report = Instant::now();
+                    reported = report_overflows(&overflow_counters, reported);
+                }
+            }
+            report_overflows(&overflow_counters, reported);
+        });
+        self.service_threads.push(reporter_thread);
+    }
+
     fn run_monitoring(
         &mut self,
         udp_socket: Arc<UdpSocket>,
@@ -154,40 +305,403 @@ impl App {
         self.service_threads.push(monitoring_thread);
     }
 
+    fn run_multicast_publisher(
+        &mut self,
+        tickers_router: &TickersRouter,
+    ) -> Result<Option<Arc<MulticastGroups>>, ServerError> {
+        let Some(config) = self.config.multicast.as_ref() else {
+            return Ok(None);
+        };
+
+        let groups = Arc::new(MulticastGroups::new(config, &self.config.tickers)?);
+        let udp_socket = UdpSocket::bind((self.config.address, 0))?;
+        udp_socket.set_multicast_loop_v4(true)?;
+
+        let quote_rx = tickers_router.add_feed()?;
+        let publisher_thread = run_multicast_publisher(
+            Arc::clone(&self.cancellation_token),
+            udp_socket,
+            Arc::clone(&groups),
+            quote_rx,
+        );
+        self.service_threads.push(publisher_thread);
+        Ok(Some(groups))
+    }
+
+    fn run_journal(
+        &mut self,
+        tickers_router: &TickersRouter,
+    ) -> Result<Option<crossbeam_channel::Sender<HistoryQuery>>, ServerError> {
+        let Some(config) = self.config.journal.clone() else {
+            return Ok(None);
+        };
+
+        let journal = Arc::new(Journal::open(config)?);
+        let quote_rx = tickers_router.add_feed()?;
+        let writer_thread = run_journal_writer(
+            Arc::clone(&self.cancellation_token),
+            Arc::clone(&journal),
+            quote_rx,
+        );
+        self.service_threads.push(writer_thread);
+
+        let (history_tx, history_rx) = crossbeam_channel::bounded(HISTORY_QUEUE_CAPACITY);
+        let reader_thread =
+            run_history_reader(Arc::clone(&self.cancellation_token), journal, history_rx);
+        self.service_threads.push(reader_thread);
+        Ok(Some(history_tx))
+    }
+
     fn run_quotes_generator(&mut self, tickers_router: Arc<TickersRouter>) {
-        let mut tickers = Vec::new();
-        mem::swap(&mut tickers, &mut self.tickers);
-        let generator_thread = run_quotes_generator(
-            tickers,
+        let cancellation_token = Arc::clone(&self.cancellation_token);
+        let generator_thread = match self.config.quote_source.take() {
+            Some(quote_rx) => run_quote_source(tickers_router, cancellation_token, quote_rx),
+            None => run_quotes_generator(tickers_router, cancellation_token),
+        };
+        self.service_threads.push(generator_thread);
+    }
+
+    fn run_tickers_reloader(
+        &mut self,
+        tickers_router: Arc<TickersRouter>,
+        multicast_groups: Option<Arc<MulticastGroups>>,
+    ) {
+        let Some(tickers_file) = self.config.tickers_file.clone() else {
+            return;
+        };
+        let reloader_thread = run_tickers_reloader(
+            tickers_file,
             tickers_router,
+            multicast_groups,
             Arc::clone(&self.cancellation_token),
         );
-        self.service_threads.push(generator_thread);
+        self.service_threads.push(reloader_thread);
     }
 
-    fn run_listening(
+    fn run_stream_quotes(
         &mut self,
-        tcp_listener: TcpListener,
         udp_socket: Arc<UdpSocket>,
         tickers_router: Arc<TickersRouter>,
         monitoring_router: Arc<MonitoringRouter>,
-    ) -> Receiver<JoinHandle<Option<ClientAddress>>> {
-        let context = ListenContext::new(
-            tcp_listener,
+    ) -> Arc<StreamQuotesPool> {
+        let context = StreamQuotesContext::new(
             Arc::clone(&self.cancellation_token),
             udp_socket,
             tickers_router,
             monitoring_router,
         );
 
-        let (thread_rx, listen_thread) = run_listening(context);
+        let (pool, worker_threads) = run_stream_quotes(self.streaming_config.workers(), context);
+        self.service_threads.extend(worker_threads);
+        Arc::new(pool)
+    }
+
+    fn run_connection_handlers(
+        &mut self,
+        context: ConnectionHandlerContext,
+    ) -> crossbeam_channel::Sender<PendingConnection> {
+        let (connection_tx, connection_rx) = crossbeam_channel::unbounded();
+        let handler_threads = run_connection_handlers(
+            self.config.connection_handlers,
+            context,
+            connection_tx.clone(),
+            connection_rx,
+        );
+        self.service_threads.extend(handler_threads);
+        connection_tx
+    }
+
+    fn run_listening(
+        &mut self,
+        tcp_listener: TcpListener,
+        connection_tx: crossbeam_channel::Sender<PendingConnection>,
+    ) {
+        let context = ListenContext::new(
+            tcp_listener,
+            Arc::clone(&self.cancellation_token),
+            connection_tx,
+        );
+
+        let listen_thread = run_listening(context);
         self.service_threads.push(listen_thread);
-        thread_rx
     }
 }
 
-fn set_ctrlc_handler(cancellation_token: Arc<ServerCancellationToken>) -> Result<(), ctrlc::Error> {
-    ctrlc::set_handler(move || {
-        cancellation_token.cancel();
-    })
+/// Binds the TCP listener and the UDP socket to the same port, which clients ping.
+fn bind(address: IpAddr, port: u16) -> std::io::Result<(TcpListener, UdpSocket)> {
+    if port != 0 {
+        return Ok((
+            TcpListener::bind((address, port))?,
+            UdpSocket::bind((address, port))?,
+        ));
+    }
+
+    for _ in 0..BIND_ATTEMPTS {
+        let tcp_listener = TcpListener::bind((address, 0))?;
+        let port = tcp_listener.local_addr()?.port();
+        match UdpSocket::bind(SocketAddr::new(address, port)) {
+            Ok(udp_socket) => return Ok((tcp_listener, udp_socket)),
+            Err(e) if e.kind() == ErrorKind::AddrInUse => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(ErrorKind::AddrInUse.into())
+}
+
+fn report_overflows(counters: &OverflowCounters, reported: (u64, u64, u64)) -> (u64, u64, u64) {
+    let counters = counters.snapshot();
+    if counters != reported {
+        let (dropped, conflated, disconnected) = counters;
+        warn!(
+            "Session queue overflows: dropped={}, conflated={}, disconnected={}",
+            dropped, conflated, disconnected
+        );
+    }
+    counters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::quote_streaming::{
+        Datagram, Delivery, MAX_REQUEST_SIZE, Request, Response, SubscriptionOptions, encode_frame,
+        write_frame,
+    };
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::time::Instant;
+
+    #[test]
+    fn test_start_on_any_port() {
+        let builder = ServerBuilder::new(vec!["AAPL".to_string()])
+            .with_groups(vec![TickerGroup::new("tech", vec!["AAPL".to_string()])])
+            .with_port(0)
+            .with_workers(1);
+        let first = builder.clone().start().unwrap();
+        let second = builder.start().unwrap();
+        assert_ne!(first.local_addr(), second.local_addr());
+
+        let mut stream = TcpStream::connect(first.local_addr()).unwrap();
+        let request: Vec<u8> = Request::ListGroups.try_into().unwrap();
+        write_frame(&mut stream, &request).unwrap();
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap();
+        let Response::Groups(groups) = Response::try_from(buffer.as_slice()).unwrap() else {
+            panic!("Expected the ticker groups");
+        };
+        assert_eq!(groups[0].name(), "tech");
+
+        first.shutdown().unwrap();
+        second.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_requests_are_framed() {
+        let server = ServerBuilder::new(vec!["AAPL".to_string()])
+            .with_port(0)
+            .with_workers(1)
+            .start()
+            .unwrap();
+        let request = |bytes: &[u8]| {
+            let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+            // Split in two writes, the server must wait for the rest of the frame
+            let (head, tail) = bytes.split_at(bytes.len() / 2);
+            stream.write_all(head).unwrap();
+            stream.flush().unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            stream.write_all(tail).unwrap();
+            let mut buffer = Vec::new();
+            stream.read_to_end(&mut buffer).unwrap();
+            Response::try_from(buffer.as_slice()).unwrap()
+        };
+
+        // Far over the 1 KiB a single read used to take
+        let tickers: Vec<String> = (0..1000).map(|i| format!("T{}", i)).collect();
+        let snapshot: Vec<u8> = Request::Snapshot { tickers }.try_into().unwrap();
+        assert!(snapshot.len() > 4096);
+        assert_eq!(
+            request(&encode_frame(&snapshot)),
+            Response::Quotes(Vec::new())
+        );
+
+        let oversized = ((MAX_REQUEST_SIZE + 1) as u32).to_le_bytes();
+        assert_eq!(
+            request(&oversized),
+            Response::Error("Request is too large".to_string())
+        );
+
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_stalled_connection_does_not_block_others() {
+        let server = ServerBuilder::new(vec!["AAPL".to_string()])
+            .with_port(0)
+            .with_workers(1)
+            .with_connection_handlers(1)
+            .start()
+            .unwrap();
+        let _stalled = TcpStream::connect(server.local_addr()).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+
+        let started = Instant::now();
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+        let request: Vec<u8> = Request::ListGroups.try_into().unwrap();
+        write_frame(&mut stream, &request).unwrap();
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap();
+        assert!(matches!(
+            Response::try_from(buffer.as_slice()).unwrap(),
+            Response::Groups(_)
+        ));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_quote_source() {
+        let (quote_tx, quote_rx) = crossbeam_channel::unbounded();
+        let server = ServerBuilder::new(vec!["AAPL".to_string()])
+            .with_port(0)
+            .with_workers(1)
+            .with_quote_source(quote_rx)
+            .start()
+            .unwrap();
+        let quote = StockQuote::new("AAPL", 12345, 10, 1);
+        quote_tx.send(quote.clone()).unwrap();
+
+        let snapshot: Vec<u8> = Request::Snapshot {
+            tickers: vec!["AAPL".to_string()],
+        }
+        .try_into()
+        .unwrap();
+        let started = Instant::now();
+        loop {
+            let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+            write_frame(&mut stream, &snapshot).unwrap();
+            let mut buffer = Vec::new();
+            stream.read_to_end(&mut buffer).unwrap();
+            match Response::try_from(buffer.as_slice()).unwrap() {
+                Response::Quotes(quotes) if !quotes.is_empty() => {
+                    assert_eq!(quotes, vec![quote]);
+                    break;
+                }
+                _ => assert!(started.elapsed() < Duration::from_secs(5)),
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_retransmit_from_foreign_address_is_refused() {
+        let (quote_tx, quote_rx) = crossbeam_channel::unbounded();
+        let server = ServerBuilder::new(vec!["AAPL".to_string()])
+            .with_port(0)
+            .with_workers(1)
+            .with_quote_source(quote_rx)
+            .start()
+            .unwrap();
+        let request = |request: Request| {
+            let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+            write_frame(&mut stream, &Vec::<u8>::try_from(request).unwrap()).unwrap();
+            let mut buffer = Vec::new();
+            stream.read_to_end(&mut buffer).unwrap();
+            Response::try_from(buffer.as_slice()).unwrap()
+        };
+
+        // The session streams to another loopback address than the requests come from
+        let client = UdpSocket::bind("127.0.0.2:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_millis(300)))
+            .unwrap();
+        let session = client.local_addr().unwrap();
+        let subscribe = Request::StreamTickers {
+            ticker: vec!["AAPL".to_string()],
+            groups: Vec::new(),
+            delivery: Delivery::Unicast {
+                address: session.ip(),
+                port: session.port(),
+            },
+            options: SubscriptionOptions::default(),
+        };
+        assert_eq!(request(subscribe), Response::Ok);
+        quote_tx.send(StockQuote::new("AAPL", 100, 1, 1)).unwrap();
+        let mut buffer = [0; 1024];
+        let len = client.recv(&mut buffer).unwrap();
+        assert_eq!(Datagram::try_from(&buffer[..len]).unwrap().sequence(), 1);
+
+        let retransmit = || Request::Retransmit {
+            address: session.ip(),
+            port: session.port(),
+            from_seq: 1,
+            to_seq: 1,
+        };
+        assert_eq!(
+            request(retransmit()),
+            Response::Error("Not the session's host".to_string())
+        );
+        let foreign = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let retransmit = Vec::<u8>::try_from(retransmit()).unwrap();
+        foreign.send_to(&retransmit, server.local_addr()).unwrap();
+        assert!(client.recv(&mut buffer).is_err());
+
+        // The session's own address is answered
+        client.send_to(&retransmit, server.local_addr()).unwrap();
+        let len = client.recv(&mut buffer).unwrap();
+        assert_eq!(Datagram::try_from(&buffer[..len]).unwrap().sequence(), 1);
+
+        server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_slow_sender_does_not_block_others() {
+        let server = ServerBuilder::new(vec!["AAPL".to_string()])
+            .with_port(0)
+            .with_workers(1)
+            .with_connection_handlers(1)
+            .start()
+            .unwrap();
+        let request: Vec<u8> = Request::ListGroups.try_into().unwrap();
+
+        // Sends a byte of its request every 200 ms, until the server hangs up
+        let mut slow = TcpStream::connect(server.local_addr()).unwrap();
+        let frame = encode_frame(&request);
+        let trickle = {
+            let mut slow = slow.try_clone().unwrap();
+            std::thread::spawn(move || {
+                for byte in frame.iter().cycle() {
+                    if slow.write_all(&[*byte]).is_err() {
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_millis(200));
+                }
+            })
+        };
+        std::thread::sleep(Duration::from_millis(100));
+
+        let started = Instant::now();
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+        write_frame(&mut stream, &request).unwrap();
+        let mut buffer = Vec::new();
+        stream.read_to_end(&mut buffer).unwrap();
+        assert!(matches!(
+            Response::try_from(buffer.as_slice()).unwrap(),
+            Response::Groups(_)
+        ));
+        assert!(started.elapsed() < Duration::from_secs(4));
+
+        // The slow request was cut off at its deadline
+        slow.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let cut_off = slow.read_to_end(&mut Vec::new());
+        assert!(cut_off.map_or_else(
+            |e| !matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut),
+            |_| true
+        ));
+        trickle.join().unwrap();
+
+        server.shutdown().unwrap();
+    }
 }
@@ -1,16 +1,22 @@
-use crate::app::monitoring::MonitoringRouterError;
-use crate::app::quote_streaming::TickersRouterError;
+use crate::app::journal::JournalError;
+use crate::app::quote_streaming::{MulticastError, TickersRouterError};
 
+/// Failures to start or stop the server.
 #[derive(Debug, thiserror::Error)]
-pub(super) enum AppError {
+pub enum ServerError {
+    /// A socket could not be bound or configured.
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
-    #[error("Ctrl+C error: {0}")]
-    Ctrc(#[from] ctrlc::Error),
+    /// A service thread panicked.
     #[error("Thread join error")]
     ThreadJoin,
-    #[error("Monitoring route error: {0}")]
-    MonitoringRoute(#[from] MonitoringRouterError),
+    /// The multicast publisher or the journal could not be routed the quotes.
     #[error("Tickers route error: {0}")]
     TickersRoute(#[from] TickersRouterError),
+    /// The multicast configuration is invalid.
+    #[error("Multicast error: {0}")]
+    Multicast(#[from] MulticastError),
+    /// The journal could not be opened.
+    #[error("Journal error: {0}")]
+    Journal(#[from] JournalError),
 }
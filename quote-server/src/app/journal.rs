@@ -0,0 +1,250 @@
+mod reader;
+mod segments;
+mod writer;
+
+use crate::app::journal::segments::{Segment, list_segments, read_segment};
+use quote_streaming::StockQuote;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+pub(crate) use reader::{HistoryQuery, run_history_reader};
+pub(crate) use writer::run_journal_writer;
+
+const MAX_HISTORY_LIMIT: usize = 10_000;
+const MAX_HISTORY_SPAN: i64 = 24 * 60 * 60;
+
+/// Where and for how long the server journals quotes for history queries.
+#[derive(Debug, Clone)]
+pub struct JournalConfig {
+    dir: PathBuf,
+    segment_size: u64,
+    max_size: u64,
+    max_age: Duration,
+}
+
+impl JournalConfig {
+    /// Starts a new segment file after `segment_size` bytes and deletes the oldest segments
+    /// beyond `max_size` bytes in total or older than `max_age`.
+    pub fn new(dir: PathBuf, segment_size: u64, max_size: u64, max_age: Duration) -> Self {
+        Self {
+            dir,
+            segment_size,
+            max_size,
+            max_age,
+        }
+    }
+}
+
+/// Append-only journal of the generated quotes, split into segment files.
+pub(crate) struct Journal {
+    config: JournalConfig,
+}
+
+impl Journal {
+    pub(crate) fn open(config: JournalConfig) -> Result<Self, JournalError> {
+        std::fs::create_dir_all(&config.dir)?;
+        info!("Journaling quotes to {}", config.dir.display());
+        Ok(Self { config })
+    }
+
+    fn dir(&self) -> &Path {
+        &self.config.dir
+    }
+
+    /// Returns the latest `limit` quotes of the ticker between `from` and `to` inclusive,
+    /// oldest first. The range is cut to end now at the latest and may then span at most a day.
+    pub(crate) fn history(
+        &self,
+        ticker: &str,
+        from: i64,
+        to: i64,
+        limit: usize,
+    ) -> Result<Vec<StockQuote>, JournalError> {
+        if from > to {
+            return Err(JournalError::InvalidRange { from, to });
+        }
+        let to = to.min(unix_time());
+        if to.saturating_sub(from) > MAX_HISTORY_SPAN {
+            return Err(JournalError::RangeTooLong { from, to });
+        }
+        let times = from..=to;
+        let limit = limit.min(MAX_HISTORY_LIMIT);
+        let segments = list_segments(self.dir())?;
+        let mut quotes = VecDeque::with_capacity(limit);
+
+        // Newest first, the older segments are not read once the limit is reached
+        for (i, segment) in segments.iter().enumerate().rev() {
+            let end = segments.get(i + 1).map(Segment::start);
+            if quotes.len() == limit || end.is_some_and(|end| end < from) {
+                break;
+            }
+            if segment.start() > to {
+                continue;
+            }
+            let remaining = limit - quotes.len();
+            let mut latest = VecDeque::with_capacity(remaining);
+            read_segment(segment.path(), ticker, &times, |quote| {
+                if latest.len() == remaining {
+                    latest.pop_front();
+                }
+                latest.push_back(quote);
+            })?;
+            while let Some(quote) = latest.pop_back() {
+                quotes.push_front(quote);
+            }
+        }
+
+        Ok(quotes.into())
+    }
+
+    /// Deletes the oldest segments exceeding the size or age limits, keeping the active one.
+    fn apply_retention(&self) -> Result<(), JournalError> {
+        let segments = list_segments(self.dir())?;
+        let mut total_size: u64 = segments.iter().map(Segment::size).sum();
+        let max_age = i64::try_from(self.config.max_age.as_secs()).unwrap_or(i64::MAX);
+        let oldest_allowed = unix_time().saturating_sub(max_age);
+
+        for (segment, next) in segments.iter().zip(segments.iter().skip(1)) {
+            if total_size <= self.config.max_size && next.start() >= oldest_allowed {
+                break;
+            }
+            std::fs::remove_file(segment.path())?;
+            total_size -= segment.size();
+            info!("Removed journal segment {}", segment.path().display());
+        }
+        Ok(())
+    }
+}
+
+fn unix_time() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+/// Failures of the quote journal.
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    /// The journal directory or a segment could not be accessed.
+    #[error("Journal I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// A history query ends before it starts.
+    #[error("Invalid history range: {from} is after {to}")]
+    InvalidRange {
+        /// The Unix timestamp the range starts at.
+        from: i64,
+        /// The Unix timestamp the range ends at.
+        to: i64,
+    },
+    /// A history query spans more than a day once cut to end now.
+    #[error("History range {from}..={to} spans more than {MAX_HISTORY_SPAN} seconds")]
+    RangeTooLong {
+        /// The Unix timestamp the range starts at.
+        from: i64,
+        /// The Unix timestamp the range ends at, now at the latest.
+        to: i64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::journal::segments::{segment_path, write_record};
+    use quote_streaming::Response;
+
+    fn open_journal(name: &str, max_size: u64, max_age: Duration) -> Journal {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Journal::open(JournalConfig::new(dir, 1024, max_size, max_age)).unwrap()
+    }
+
+    fn write_segment(journal: &Journal, start: i64, quotes: &[StockQuote]) {
+        let mut bytes = Vec::new();
+        for quote in quotes {
+            let record: Vec<u8> = Response::Quote(quote.clone()).try_into().unwrap();
+            write_record(&mut bytes, quote.ticker(), quote.timestamp(), &record).unwrap();
+        }
+        std::fs::write(segment_path(journal.dir(), start), bytes).unwrap();
+    }
+
+    #[test]
+    fn test_history_range_and_limit() {
+        let journal = open_journal("journal-history-test", u64::MAX, Duration::MAX);
+        let now = unix_time();
+        let quote = |ticker, age| StockQuote::new(ticker, 100, 1, now - age);
+        write_segment(&journal, now - 40, &[quote("AAPL", 40), quote("MSFT", 35)]);
+        write_segment(&journal, now - 30, &[quote("AAPL", 30), quote("AAPL", 20)]);
+        write_segment(&journal, now - 10, &[quote("AAPL", 10), quote("MSFT", 5)]);
+
+        let history = journal.history("AAPL", now - 35, now - 15, 10).unwrap();
+        assert_eq!(history, vec![quote("AAPL", 30), quote("AAPL", 20)]);
+        let history = journal.history("AAPL", now - MAX_HISTORY_SPAN, i64::MAX, 3);
+        assert_eq!(
+            history.unwrap(),
+            vec![quote("AAPL", 30), quote("AAPL", 20), quote("AAPL", 10)]
+        );
+        let history = journal.history(
+            "AAPL",
+            now - 2 * MAX_HISTORY_SPAN,
+            now - MAX_HISTORY_SPAN,
+            10,
+        );
+        assert!(history.unwrap().is_empty());
+        assert!(matches!(
+            journal.history("AAPL", now, now - 10, 10),
+            Err(JournalError::InvalidRange { .. })
+        ));
+        assert!(matches!(
+            journal.history("AAPL", now - MAX_HISTORY_SPAN - 1, i64::MAX, 10),
+            Err(JournalError::RangeTooLong { .. })
+        ));
+        assert!(matches!(
+            journal.history("AAPL", i64::MIN, now, 10),
+            Err(JournalError::RangeTooLong { .. })
+        ));
+
+        std::fs::remove_dir_all(journal.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_apply_retention() {
+        let now = unix_time();
+        let quote = |age| StockQuote::new("AAPL", 100, 1, now - age);
+        let record: Vec<u8> = Response::Quote(quote(0)).try_into().unwrap();
+        let segment_size = write_record(&mut Vec::new(), "AAPL", now, &record).unwrap();
+        let starts = |journal: &Journal| -> Vec<i64> {
+            let segments = list_segments(journal.dir()).unwrap();
+            segments.iter().map(Segment::start).collect()
+        };
+
+        // A segment is too old once the next one starts before the maximum age
+        let journal = open_journal("journal-age-test", u64::MAX, Duration::from_secs(3600));
+        for age in [7200, 7100, 60, 50] {
+            write_segment(&journal, now - age, &[quote(age)]);
+        }
+        journal.apply_retention().unwrap();
+        assert_eq!(starts(&journal), vec![now - 7100, now - 60, now - 50]);
+        std::fs::remove_dir_all(journal.dir()).unwrap();
+
+        let journal = open_journal("journal-size-test", 2 * segment_size, Duration::MAX);
+        for age in [60, 50, 40] {
+            write_segment(&journal, now - age, &[quote(age)]);
+        }
+        journal.apply_retention().unwrap();
+        assert_eq!(starts(&journal), vec![now - 50, now - 40]);
+
+        // The active segment is kept however large it is
+        let journal = Journal::open(JournalConfig::new(
+            journal.dir().to_path_buf(),
+            1024,
+            0,
+            Duration::ZERO,
+        ))
+        .unwrap();
+        journal.apply_retention().unwrap();
+        assert_eq!(starts(&journal), vec![now - 40]);
+        std::fs::remove_dir_all(journal.dir()).unwrap();
+    }
+}
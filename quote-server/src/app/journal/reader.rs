@@ -0,0 +1,91 @@
+use crate::app::journal::{Journal, JournalError};
+use crate::app::server_cancellation_token::ServerCancellationToken;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use quote_streaming::Response;
+use std::io::Write;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{error, info, instrument, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A history request waiting for its answer on the connection it came in on.
+pub(crate) struct HistoryQuery {
+    stream: TcpStream,
+    ticker: String,
+    from: i64,
+    to: i64,
+    limit: usize,
+}
+
+impl HistoryQuery {
+    pub(crate) fn new(stream: TcpStream, ticker: String, from: i64, to: i64, limit: usize) -> Self {
+        Self {
+            stream,
+            ticker,
+            from,
+            to,
+            limit,
+        }
+    }
+
+    /// Gives back the connection of a query that could not be queued.
+    pub(crate) fn into_stream(self) -> TcpStream {
+        self.stream
+    }
+}
+
+/// Answers history queries on a thread of its own, so reading the segments does not hold up
+/// the connection handlers.
+#[instrument(name = "Run history reader", skip_all)]
+pub(crate) fn run_history_reader(
+    cancellation_token: Arc<ServerCancellationToken>,
+    journal: Arc<Journal>,
+    query_rx: Receiver<HistoryQuery>,
+) -> JoinHandle<()> {
+    thread::spawn(move || read_history(cancellation_token, journal, query_rx))
+}
+
+#[instrument(name = "Read history", skip_all)]
+fn read_history(
+    cancellation_token: Arc<ServerCancellationToken>,
+    journal: Arc<Journal>,
+    query_rx: Receiver<HistoryQuery>,
+) {
+    while !cancellation_token.is_cancelled() {
+        match query_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(query) => answer(&journal, query),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                info!("History queries receiver disconnected");
+                break;
+            }
+        }
+    }
+}
+
+fn answer(journal: &Journal, mut query: HistoryQuery) {
+    let response = match journal.history(&query.ticker, query.from, query.to, query.limit) {
+        Ok(quotes) => Response::Quotes(quotes),
+        Err(e @ (JournalError::InvalidRange { .. } | JournalError::RangeTooLong { .. })) => {
+            Response::Error(e.to_string())
+        }
+        Err(e) => {
+            error!("Failed to read history: {}", e);
+            Response::Error("Failed to read history".to_string())
+        }
+    };
+    let response: Vec<u8> = match response.try_into() {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to serialize history: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = query.stream.write_all(&response) {
+        warn!("Failed to send history: {}", e);
+    }
+}
@@ -0,0 +1,193 @@
+use quote_streaming::{Response, StockQuote};
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+const SEGMENT_EXTENSION: &str = "journal";
+const LENGTH_SIZE: usize = size_of::<u32>();
+/// The timestamp and the ticker length that precede the ticker in every record.
+const HEADER_SIZE: usize = size_of::<i64>() + size_of::<u8>();
+
+/// A journal file holding the quotes generated since `start` until the next segment starts.
+#[derive(Debug, Clone)]
+pub(super) struct Segment {
+    path: PathBuf,
+    start: i64,
+    size: u64,
+}
+
+impl Segment {
+    pub(super) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(super) fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub(super) fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+pub(super) fn segment_path(dir: &Path, start: i64) -> PathBuf {
+    dir.join(format!("{:020}.{}", start, SEGMENT_EXTENSION))
+}
+
+/// Lists the segments of the journal directory, oldest first.
+pub(super) fn list_segments(dir: &Path) -> std::io::Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_none_or(|extension| extension != SEGMENT_EXTENSION)
+        {
+            continue;
+        }
+        let Some(start) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+        else {
+            continue;
+        };
+        let size = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            // Removed by the retention in the meantime
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        segments.push(Segment { path, start, size });
+    }
+    segments.sort_by_key(|segment| segment.start);
+    Ok(segments)
+}
+
+/// Appends a length-prefixed record of a quote, headed by its timestamp and ticker so readers
+/// can skip it without deserializing it, returning the number of bytes written.
+pub(super) fn write_record<W: Write>(
+    writer: &mut W,
+    ticker: &str,
+    timestamp: i64,
+    record: &[u8],
+) -> std::io::Result<u64> {
+    let ticker_len = u8::try_from(ticker.len()).map_err(std::io::Error::other)?;
+    let len = HEADER_SIZE + ticker.len() + record.len();
+    writer.write_all(
+        &u32::try_from(len)
+            .map_err(std::io::Error::other)?
+            .to_le_bytes(),
+    )?;
+    writer.write_all(&timestamp.to_le_bytes())?;
+    writer.write_all(&[ticker_len])?;
+    writer.write_all(ticker.as_bytes())?;
+    writer.write_all(record)?;
+    Ok((LENGTH_SIZE + len) as u64)
+}
+
+/// Passes the quotes of `ticker` within `times` to `on_quote`, oldest first. The records of
+/// other tickers and times are skipped unread, and reading stops at a record that is still
+/// being written.
+pub(super) fn read_segment(
+    path: &Path,
+    ticker: &str,
+    times: &RangeInclusive<i64>,
+    mut on_quote: impl FnMut(StockQuote),
+) -> std::io::Result<()> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let mut reader = BufReader::new(file);
+    let mut header = [0; LENGTH_SIZE + HEADER_SIZE];
+    let mut record_ticker = [0; u8::MAX as usize];
+
+    loop {
+        if !read_full(&mut reader, &mut header)? {
+            return Ok(());
+        }
+        let (len, header) = header.split_at(LENGTH_SIZE);
+        let (timestamp, ticker_len) = header.split_at(size_of::<i64>());
+        let len = u32::from_le_bytes(len.try_into().expect("length prefix is 4 bytes")) as usize;
+        let timestamp = i64::from_le_bytes(timestamp.try_into().expect("timestamp is 8 bytes"));
+        let ticker_len = ticker_len[0] as usize;
+        let Some(record_len) = len.checked_sub(HEADER_SIZE + ticker_len) else {
+            warn!("Invalid record in {}", path.display());
+            return Ok(());
+        };
+
+        if !times.contains(&timestamp) {
+            reader.seek_relative((ticker_len + record_len) as i64)?;
+            continue;
+        }
+        let record_ticker = &mut record_ticker[..ticker_len];
+        if !read_full(&mut reader, record_ticker)? {
+            return Ok(());
+        }
+        if record_ticker != ticker.as_bytes() {
+            reader.seek_relative(record_len as i64)?;
+            continue;
+        }
+
+        let mut record = vec![0; record_len];
+        if !read_full(&mut reader, &mut record)? {
+            return Ok(());
+        }
+        match Response::try_from(record.as_slice()) {
+            Ok(Response::Quote(quote)) => on_quote(quote),
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Invalid record in {}: {}", path.display(), e);
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Fills `buf`, returning false at the end of the segment or of the records written so far.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_quote(bytes: &mut Vec<u8>, quote: &StockQuote) {
+        let record: Vec<u8> = Response::Quote(quote.clone()).try_into().unwrap();
+        write_record(bytes, quote.ticker(), quote.timestamp(), &record).unwrap();
+    }
+
+    #[test]
+    fn test_read_segment_skips_partial_record() {
+        let dir = std::env::temp_dir().join(format!("journal-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = segment_path(&dir, 100);
+
+        let quote = StockQuote::new("AAPL", 100, 1, 100);
+        let mut bytes = Vec::new();
+        write_quote(&mut bytes, &StockQuote::new("MSFT", 100, 1, 100));
+        write_quote(&mut bytes, &quote);
+        write_quote(&mut bytes, &StockQuote::new("AAPL", 100, 1, 200));
+        write_quote(&mut bytes, &quote);
+        bytes.truncate(bytes.len() - 1);
+        std::fs::write(&path, bytes).unwrap();
+
+        let mut quotes = Vec::new();
+        read_segment(&path, "AAPL", &(0..=150), |quote| quotes.push(quote)).unwrap();
+        assert_eq!(quotes, vec![quote]);
+        let segments = list_segments(&dir).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].start(), 100);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
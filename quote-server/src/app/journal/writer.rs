@@ -0,0 +1,105 @@
+use crate::app::journal::segments::{segment_path, write_record};
+use crate::app::journal::{Journal, JournalError};
+use crate::app::quote_streaming::SharedQuote;
+use crate::app::server_cancellation_token::ServerCancellationToken;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{instrument, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60);
+
+struct ActiveSegment {
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+#[instrument(name = "Run journal writer", skip_all)]
+pub(crate) fn run_journal_writer(
+    cancellation_token: Arc<ServerCancellationToken>,
+    journal: Arc<Journal>,
+    quote_rx: Receiver<SharedQuote>,
+) -> JoinHandle<()> {
+    thread::spawn(move || write_journal(cancellation_token, journal, quote_rx))
+}
+
+#[instrument(name = "Write journal", skip_all)]
+fn write_journal(
+    cancellation_token: Arc<ServerCancellationToken>,
+    journal: Arc<Journal>,
+    quote_rx: Receiver<SharedQuote>,
+) {
+    let mut active = None;
+    let mut last_retention = Instant::now();
+
+    while !cancellation_token.is_cancelled() {
+        match quote_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(quote) => {
+                let quotes = std::iter::once(quote).chain(quote_rx.try_iter());
+                if let Err(e) = append(&journal, &mut active, quotes) {
+                    warn!("Failed to write journal: {}", e);
+                    active = None;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                warn!("Quotes receiver disconnected");
+                break;
+            }
+        }
+
+        if last_retention.elapsed() >= RETENTION_INTERVAL {
+            last_retention = Instant::now();
+            if let Err(e) = journal.apply_retention() {
+                warn!("Failed to apply journal retention: {}", e);
+            }
+        }
+    }
+}
+
+fn append(
+    journal: &Journal,
+    active: &mut Option<ActiveSegment>,
+    quotes: impl Iterator<Item = SharedQuote>,
+) -> Result<(), JournalError> {
+    for quote in quotes {
+        if active
+            .as_ref()
+            .is_some_and(|segment| segment.size >= journal.config.segment_size)
+        {
+            *active = None;
+            journal.apply_retention()?;
+        }
+        let segment = match active {
+            Some(segment) => segment,
+            None => active.insert(open_segment(journal, quote.quote().timestamp())?),
+        };
+        let record = quote.quote();
+        segment.size += write_record(
+            &mut segment.writer,
+            record.ticker(),
+            record.timestamp(),
+            quote.bytes(),
+        )?;
+    }
+
+    if let Some(segment) = active.as_mut() {
+        segment.writer.flush()?;
+    }
+    Ok(())
+}
+
+fn open_segment(journal: &Journal, start: i64) -> Result<ActiveSegment, JournalError> {
+    let path = segment_path(journal.dir(), start);
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let size = file.metadata()?.len();
+    Ok(ActiveSegment {
+        writer: BufWriter::new(file),
+        size,
+    })
+}
@@ -1,51 +1,73 @@
 use crate::app::ServerCancellationToken;
 use crate::app::client_address::ClientAddress;
-use crate::app::monitoring::{MonitoringRouter, MonitoringRouterError};
+use crate::app::journal::HistoryQuery;
+use crate::app::monitoring::{MonitoringRouter, MonitoringRouterError, SessionEvent};
 use crate::app::quote_streaming::{
-    StreamQuotesContext, TickersRouter, TickersRouterError, stream_quotes,
+    MulticastGroups, QuoteSender, Session, SessionQueue, SharedQuote, StreamQuotesPool,
+    StreamQuotesPoolError, StreamingConfig, TickersRouter, TickersRouterError,
 };
-use crossbeam_channel::Sender;
-use quote_streaming::{Request, Response, StockQuote};
-use std::io::{Read, Write};
-use std::net::UdpSocket;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use quote_streaming::{
+    Datagram, Delivery, MAX_REQUEST_SIZE, Request, Response, SubscriptionOptions,
+    read_frame_limited, write_frame,
+};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{IpAddr, TcpStream};
 use std::sync::Arc;
-use std::thread;
-use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tracing::{error, info, instrument};
 use tracing_log::log::warn;
 
+/// How long a client may take to send the whole request, however it spaces out its bytes.
+const REQUEST_DEADLINE: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
 pub(crate) struct ConnectionHandlerContext {
     cancellation_token: Arc<ServerCancellationToken>,
-    udp_socket: Arc<UdpSocket>,
     tickers_router: Arc<TickersRouter>,
     monitoring_router: Arc<MonitoringRouter>,
-    thread_tx: Sender<JoinHandle<Option<ClientAddress>>>,
+    multicast_groups: Option<Arc<MulticastGroups>>,
+    stream_quotes_pool: Arc<StreamQuotesPool>,
+    session_queue: SessionQueue,
+    streaming_config: StreamingConfig,
+    history_tx: Option<Sender<HistoryQuery>>,
 }
 
 impl ConnectionHandlerContext {
     pub(crate) fn new(
         cancellation_token: Arc<ServerCancellationToken>,
-        udp_socket: Arc<UdpSocket>,
         tickers_router: Arc<TickersRouter>,
         monitoring_router: Arc<MonitoringRouter>,
-        thread_tx: Sender<JoinHandle<Option<ClientAddress>>>,
+        multicast_groups: Option<Arc<MulticastGroups>>,
+        stream_quotes_pool: Arc<StreamQuotesPool>,
+        session_queue: SessionQueue,
+        streaming_config: StreamingConfig,
     ) -> Self {
         Self {
             cancellation_token,
-            udp_socket,
             tickers_router,
             monitoring_router,
-            thread_tx,
+            multicast_groups,
+            stream_quotes_pool,
+            session_queue,
+            streaming_config,
+            history_tx: None,
         }
     }
+
+    pub(crate) fn with_history(mut self, history_tx: Option<Sender<HistoryQuery>>) -> Self {
+        self.history_tx = history_tx;
+        self
+    }
+
+    pub(super) fn is_cancelled(&self) -> bool {
+        self.cancellation_token.is_cancelled()
+    }
 }
 
 #[instrument(name = "Handle connection", skip_all)]
-pub(super) fn handle_connection<R: Read + Write>(
-    mut stream: R,
-    context: ConnectionHandlerContext,
-) -> Option<ClientAddress> {
-    let request = match read_request(&mut stream) {
+pub(super) fn handle_connection(mut stream: TcpStream, context: &ConnectionHandlerContext) {
+    let request = match read_request(DeadlineReader::new(&stream, REQUEST_DEADLINE)) {
         Ok(request) => {
             info!("Received request: {:?}", request);
             request
@@ -54,48 +76,338 @@ pub(super) fn handle_connection<R: Read + Write>(
             warn!("Invalid request: {}", e);
             let response = Response::Error("Invalid request".to_string());
             send_response(&mut stream, response, &context.cancellation_token);
-            return None;
+            return;
+        }
+        Err(ReadRequestError::Io(e)) if e.kind() == ErrorKind::InvalidData => {
+            warn!("Rejected request: {}", e);
+            let response = Response::Error("Request is too large".to_string());
+            send_response(&mut stream, response, &context.cancellation_token);
+            return;
         }
         Err(e) => {
             warn!("Failed to read request: {}", e);
-            return None;
+            return;
         }
     };
 
     match request {
         Request::StreamTickers {
             ticker,
-            port,
+            groups,
+            delivery,
+            options,
+        } => {
+            let tickers = match context.tickers_router.expand_groups(ticker, &groups) {
+                Ok(tickers) => tickers,
+                Err(e) => {
+                    warn!("Rejected subscription: {}", e);
+                    let response = Response::Error(e.to_string());
+                    if delivery == Delivery::Tcp {
+                        send_framed_response(&mut stream, response, &context.cancellation_token);
+                    } else {
+                        send_response(&mut stream, response, &context.cancellation_token);
+                    }
+                    return;
+                }
+            };
+            let response = match delivery {
+                Delivery::Multicast => multicast_groups(&tickers, &options, context),
+                Delivery::Unicast { address, port } if !is_unicast_destination(address, port) => {
+                    warn!("Rejected unicast delivery to {}:{}", address, port);
+                    Response::Error(format!(
+                        "Cannot stream unicast quotes to {}:{}",
+                        address, port
+                    ))
+                }
+                Delivery::Unicast { address, port } => {
+                    let client_address = ClientAddress::new(address, port);
+                    let result = start_stream_quotes(
+                        client_address.clone(),
+                        tickers,
+                        options,
+                        SessionTransport::Udp,
+                        context,
+                    );
+                    if let Err(e) = result {
+                        error!("Failed to start stream quotes: {}", e);
+                        context.cancellation_token.cancel();
+                        return;
+                    }
+                    Response::Ok
+                }
+                Delivery::Tcp => {
+                    stream_over_tcp(stream, tickers, options, context);
+                    return;
+                }
+                Delivery::PingSource => {
+                    match stream_to_ping_source(&stream, tickers, options, context) {
+                        Some(token) => Response::SessionToken(token),
+                        None => return,
+                    }
+                }
+            };
+            send_response(&mut stream, response, &context.cancellation_token);
+        }
+        Request::ListGroups => {
+            let response = Response::Groups(context.tickers_router.groups().to_vec());
+            send_response(&mut stream, response, &context.cancellation_token);
+        }
+        Request::Snapshot { tickers } => {
+            let response = match context.tickers_router.last_quotes(&tickers) {
+                Ok(quotes) => Response::Quotes(quotes),
+                Err(e) => {
+                    error!("Failed to get last quotes: {}", e);
+                    Response::Error("Failed to get last quotes".to_string())
+                }
+            };
+            send_response(&mut stream, response, &context.cancellation_token);
+        }
+        Request::History {
+            ticker,
+            from,
+            to,
+            limit,
+        } => {
+            let Some(history_tx) = context.history_tx.as_ref() else {
+                let response = Response::Error("History is not enabled".to_string());
+                send_response(&mut stream, response, &context.cancellation_token);
+                return;
+            };
+            let query = HistoryQuery::new(stream, ticker, from, to, limit as usize);
+            match history_tx.try_send(query) {
+                Ok(()) => {}
+                Err(TrySendError::Full(query)) => {
+                    warn!("Too many pending history queries");
+                    let response = Response::Error("History is busy, try again".to_string());
+                    send_response(
+                        &mut query.into_stream(),
+                        response,
+                        &context.cancellation_token,
+                    );
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    error!("History reader stopped");
+                    context.cancellation_token.cancel();
+                }
+            }
+        }
+        Request::Retransmit {
             address,
+            port,
+            from_seq,
+            to_seq,
         } => {
+            if !is_session_host(&stream, address) {
+                warn!("Rejected retransmission for {}:{}", address, port);
+                let response = Response::Error("Not the session's host".to_string());
+                send_response(&mut stream, response, &context.cancellation_token);
+                return;
+            }
             let client_address = ClientAddress::new(address, port);
-            let cancellation_token = Arc::clone(&context.cancellation_token);
-            let result = start_stream_quotes(client_address.clone(), ticker, context);
-            if let Err(e) = result {
-                error!("Failed to start stream quotes: {}", e);
-                cancellation_token.cancel();
-                return Some(client_address);
+            let response =
+                match context
+                    .monitoring_router
+                    .send_retransmit(&client_address, from_seq, to_seq)
+                {
+                    Ok(true) => Response::Ok,
+                    Ok(false) => Response::Error("Unknown session".to_string()),
+                    Err(e) => {
+                        error!("Failed to request retransmission: {}", e);
+                        Response::Error("Failed to request retransmission".to_string())
+                    }
+                };
+            send_response(&mut stream, response, &context.cancellation_token);
+        }
+        Request::ChangeSubscription {
+            address,
+            port,
+            change,
+        } => {
+            if !is_session_host(&stream, address) {
+                warn!("Rejected subscription change for {}:{}", address, port);
+                let response = Response::Error("Not the session's host".to_string());
+                send_response(&mut stream, response, &context.cancellation_token);
+                return;
             }
-            let response = Response::Ok;
-            send_response(&mut stream, response, &cancellation_token);
-            None
+            let client_address = ClientAddress::new(address, port);
+            let response = match context
+                .monitoring_router
+                .send_change(&client_address, change)
+            {
+                Ok(true) => Response::Ok,
+                Ok(false) => Response::Error("Unknown session".to_string()),
+                Err(e) => {
+                    error!("Failed to change subscription: {}", e);
+                    Response::Error("Failed to change subscription".to_string())
+                }
+            };
+            send_response(&mut stream, response, &context.cancellation_token);
         }
-        Request::Ping => {
+        Request::Ping | Request::SessionPing { .. } => {
             let response = Response::Error("Unexpected request".to_string());
             send_response(&mut stream, response, &context.cancellation_token);
-            None
         }
     }
 }
 
+/// Only the host a session streams to may change it or have its datagrams sent again, so no
+/// other host can direct the server's datagrams at it.
+fn is_session_host(stream: &TcpStream, address: IpAddr) -> bool {
+    stream.peer_addr().is_ok_and(|peer| peer.ip() == address)
+}
+
+/// Multicast group addresses are published to by the server itself, and datagrams
+/// cannot be sent to unspecified or broadcast addresses nor to port 0.
+fn is_unicast_destination(address: IpAddr, port: u16) -> bool {
+    let broadcast = match address {
+        IpAddr::V4(address) => address.is_broadcast(),
+        IpAddr::V6(_) => false,
+    };
+    port != 0 && !address.is_unspecified() && !address.is_multicast() && !broadcast
+}
+
+fn multicast_groups(
+    tickers: &[String],
+    options: &SubscriptionOptions,
+    context: &ConnectionHandlerContext,
+) -> Response {
+    match context.multicast_groups.as_ref() {
+        _ if *options != SubscriptionOptions::default() => Response::Error(
+            "Subscription options are not supported with multicast delivery".to_string(),
+        ),
+        Some(groups) => match context.tickers_router.resolve(tickers) {
+            Ok(tickers) => Response::MulticastGroups(groups.groups_for(&tickers)),
+            Err(e) => {
+                error!("Failed to resolve tickers: {}", e);
+                Response::Error("Failed to resolve tickers".to_string())
+            }
+        },
+        None => Response::Error("Multicast is not enabled".to_string()),
+    }
+}
+
+/// Keeps the connection open and hands it over to a session streaming the quotes through it.
+fn stream_over_tcp(
+    stream: TcpStream,
+    tickers: Vec<String>,
+    options: SubscriptionOptions,
+    context: &ConnectionHandlerContext,
+) {
+    let client_address = match stream.peer_addr() {
+        Ok(addr) => ClientAddress::new(addr.ip(), addr.port()),
+        Err(e) => {
+            warn!("Failed to get peer address: {}", e);
+            return;
+        }
+    };
+
+    match start_stream_quotes(
+        client_address.clone(),
+        tickers,
+        options,
+        SessionTransport::Tcp(stream),
+        context,
+    ) {
+        Ok(()) => info!("Streaming quotes over TCP to {}", client_address),
+        Err(StreamQuotesError::Connection(e)) => {
+            warn!(
+                "Failed to stream quotes over TCP to {}: {}",
+                client_address, e
+            );
+        }
+        Err(e) => {
+            error!("Failed to start stream quotes: {}", e);
+            context.cancellation_token.cancel();
+        }
+    }
+}
+
+/// Starts a session waiting for the first ping with the returned token to learn where to
+/// stream the quotes.
+fn stream_to_ping_source(
+    stream: &TcpStream,
+    tickers: Vec<String>,
+    options: SubscriptionOptions,
+    context: &ConnectionHandlerContext,
+) -> Option<u64> {
+    // The session's routes are registered with the address of the subscribing connection
+    let client_address = match stream.peer_addr() {
+        Ok(addr) => ClientAddress::new(addr.ip(), addr.port()),
+        Err(e) => {
+            warn!("Failed to get peer address: {}", e);
+            return None;
+        }
+    };
+
+    let token = rand::random();
+    let result = start_stream_quotes(
+        client_address.clone(),
+        tickers,
+        options,
+        SessionTransport::PingSource { token },
+        context,
+    );
+    if let Err(e) = result {
+        error!("Failed to start stream quotes: {}", e);
+        context.cancellation_token.cancel();
+        return None;
+    }
+    Some(token)
+}
+
+/// How a new session delivers its quotes.
+enum SessionTransport {
+    /// UDP datagrams to the session's address.
+    Udp,
+    /// Frames on the subscribing connection.
+    Tcp(TcpStream),
+    /// UDP datagrams to the source of the first ping carrying the token.
+    PingSource { token: u64 },
+}
+
 fn start_stream_quotes(
     client_address: ClientAddress,
     tickers: Vec<String>,
-    context: ConnectionHandlerContext,
+    options: SubscriptionOptions,
+    transport: SessionTransport,
+    context: &ConnectionHandlerContext,
 ) -> Result<(), StreamQuotesError> {
-    let (quote_tx, quote_rx) = crossbeam_channel::unbounded::<StockQuote>();
-    let (monitoring_tx, monitoring_rx) = crossbeam_channel::unbounded::<()>();
+    let (quote_tx, quote_rx) = context.session_queue.channel();
+    let (monitoring_tx, monitoring_rx) = crossbeam_channel::unbounded::<SessionEvent>();
+    let session = Session::new(
+        client_address.clone(),
+        quote_rx.clone(),
+        monitoring_tx.clone(),
+        monitoring_rx,
+        options.conflation(),
+        context.streaming_config.rate_limit(options.max_rate()),
+        options.filters(),
+    );
+
+    let result = open_session(
+        session,
+        &client_address,
+        tickers,
+        quote_tx,
+        monitoring_tx.clone(),
+        transport,
+        context,
+    );
+    if result.is_err() {
+        delete_routes(&client_address, quote_rx, &monitoring_tx, context);
+    }
+    result
+}
 
+fn open_session(
+    session: Session,
+    client_address: &ClientAddress,
+    tickers: Vec<String>,
+    quote_tx: QuoteSender,
+    monitoring_tx: Sender<SessionEvent>,
+    transport: SessionTransport,
+    context: &ConnectionHandlerContext,
+) -> Result<(), StreamQuotesError> {
     context
         .tickers_router
         .add_routes(tickers, quote_tx, client_address.clone())?;
@@ -103,22 +415,47 @@ fn start_stream_quotes(
         .monitoring_router
         .add_route(client_address.clone(), monitoring_tx)?;
 
-    let stream_quotes_ctx = StreamQuotesContext::new(
-        context.cancellation_token,
-        context.udp_socket,
-        quote_rx,
-        monitoring_rx,
-        client_address.clone(),
-    );
-    let thread = thread::spawn(move || stream_quotes(stream_quotes_ctx));
-    context
-        .thread_tx
-        .send(thread)
-        .map_err(|e| StreamQuotesError::SendChannel(e.to_string()))?;
+    let session = match transport {
+        SessionTransport::Udp => session,
+        SessionTransport::Tcp(mut stream) => {
+            // The answer precedes the snapshot quotes already queued for the session
+            let ok: Vec<u8> = Response::Ok.try_into()?;
+            write_frame(&mut stream, &Datagram::encode(Datagram::UNSEQUENCED, &ok))?;
+            session.with_tcp_stream(stream)?
+        }
+        SessionTransport::PingSource { token } => {
+            context
+                .monitoring_router
+                .add_token(token, client_address.clone())?;
+            session.awaiting_ping()
+        }
+    };
+    context.stream_quotes_pool.add_session(session)?;
 
     Ok(())
 }
 
+/// Deletes the routes of a session that failed to start.
+fn delete_routes(
+    client_address: &ClientAddress,
+    quote_rx: Receiver<SharedQuote>,
+    monitoring_tx: &Sender<SessionEvent>,
+    context: &ConnectionHandlerContext,
+) {
+    if let Err(e) = context
+        .monitoring_router
+        .delete(client_address, monitoring_tx)
+    {
+        warn!("Failed to delete monitoring route: {}", e);
+    }
+    if let Err(e) = context
+        .tickers_router
+        .delete_clients(vec![(client_address.clone(), quote_rx)])
+    {
+        warn!("Failed to delete ticker routes: {}", e);
+    }
+}
+
 fn send_response<W: Write>(
     writer: &mut W,
     response: Response,
@@ -139,13 +476,64 @@ fn send_response<W: Write>(
     }
 }
 
+/// Sends the response the way a TCP streaming client expects every message: framed.
+fn send_framed_response(
+    stream: &mut TcpStream,
+    response: Response,
+    cancellation_token: &Arc<ServerCancellationToken>,
+) {
+    let response: Result<Vec<u8>, _> = response.try_into();
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to serialize response: {}", e);
+            cancellation_token.cancel();
+            return;
+        }
+    };
+
+    let datagram = Datagram::encode(Datagram::UNSEQUENCED, &response);
+    if let Err(e) = write_frame(stream, &datagram) {
+        warn!("Failed to send response: {}", e);
+    }
+}
+
 fn read_request<R: Read>(mut reader: R) -> Result<Request, ReadRequestError> {
-    let mut buffer = [0; 1024];
-    let len = reader.read(&mut buffer)?;
-    let command = Request::try_from(&buffer[..len])?;
+    let payload = read_frame_limited(&mut reader, MAX_REQUEST_SIZE)?;
+    let command = Request::try_from(payload.as_slice())?;
     Ok(command)
 }
 
+/// Reads from the stream until the deadline, waiting no longer than the time left for
+/// each read.
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl<'a> DeadlineReader<'a> {
+    fn new(stream: &'a TcpStream, timeout: Duration) -> Self {
+        Self {
+            stream,
+            deadline: Instant::now() + timeout,
+        }
+    }
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(std::io::Error::new(
+                ErrorKind::TimedOut,
+                "request deadline passed",
+            ));
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 enum ReadRequestError {
     #[error("Failed to read request: {0}")]
@@ -160,6 +548,39 @@ enum StreamQuotesError {
     TickersRouter(#[from] TickersRouterError),
     #[error("Failed to add monitoring route: {0}")]
     MonitoringRouter(#[from] MonitoringRouterError),
-    #[error("Failed to send thread message: {0}")]
-    SendChannel(String),
+    #[error("Failed to add session: {0}")]
+    StreamQuotesPool(#[from] StreamQuotesPoolError),
+    #[error("Failed to serialize response: {0}")]
+    Serialization(#[from] rancor::Error),
+    #[error("Failed to set up the connection: {0}")]
+    Connection(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn test_reserved_unicast_destinations() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert!(is_unicast_destination(localhost, 34254));
+        assert!(!is_unicast_destination(localhost, 0));
+        assert!(!is_unicast_destination(
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            34254
+        ));
+        assert!(!is_unicast_destination(
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            34254
+        ));
+        assert!(!is_unicast_destination(
+            IpAddr::V4(Ipv4Addr::BROADCAST),
+            34254
+        ));
+        assert!(!is_unicast_destination(
+            IpAddr::V4(Ipv4Addr::new(239, 255, 0, 1)),
+            34254
+        ));
+    }
 }
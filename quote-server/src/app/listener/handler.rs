@@ -1,43 +1,92 @@
-use crate::app::ServerCancellationToken;
-use crate::app::client_address::ClientAddress;
 use crate::app::listener::connection_handler::{ConnectionHandlerContext, handle_connection};
-use crate::app::monitoring::MonitoringRouter;
-use crate::app::quote_streaming::TickersRouter;
-use crossbeam_channel::Sender;
-use std::net::{TcpStream, UdpSocket};
-use std::sync::Arc;
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use std::io::ErrorKind;
+use std::net::TcpStream;
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tracing::{info, instrument, warn};
 
-pub(crate) struct AcceptConnectionContext {
-    cancellation_token: Arc<ServerCancellationToken>,
-    udp_socket: Arc<UdpSocket>,
-    tickers_router: Arc<TickersRouter>,
-    monitoring_router: Arc<MonitoringRouter>,
-    thread_tx: Sender<JoinHandle<Option<ClientAddress>>>,
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long a handler waits for the request of a connection before serving the next one.
+const FIRST_BYTE_WAIT: Duration = Duration::from_millis(10);
+/// How long a connection may go without sending its request before it is dropped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An accepted connection whose request has not been read yet.
+pub(crate) struct PendingConnection {
+    stream: TcpStream,
+    accepted: Instant,
 }
 
-impl AcceptConnectionContext {
-    pub(crate) fn new(
-        cancellation_token: Arc<ServerCancellationToken>,
-        udp_socket: Arc<UdpSocket>,
-        tickers_router: Arc<TickersRouter>,
-        monitoring_router: Arc<MonitoringRouter>,
-        thread_tx: Sender<JoinHandle<Option<ClientAddress>>>,
-    ) -> Self {
+impl PendingConnection {
+    pub(crate) fn new(stream: TcpStream) -> Self {
         Self {
-            cancellation_token,
-            udp_socket,
-            tickers_router,
-            monitoring_router,
-            thread_tx,
+            stream,
+            accepted: Instant::now(),
+        }
+    }
+}
+
+#[instrument(
+    name = "Run connection handlers",
+    skip(context, connection_tx, connection_rx)
+)]
+pub(crate) fn run_connection_handlers(
+    workers: usize,
+    context: ConnectionHandlerContext,
+    connection_tx: Sender<PendingConnection>,
+    connection_rx: Receiver<PendingConnection>,
+) -> Vec<JoinHandle<()>> {
+    (0..workers)
+        .map(|_| {
+            let context = context.clone();
+            let connection_tx = connection_tx.clone();
+            let connection_rx = connection_rx.clone();
+            thread::spawn(move || handle_connections(context, connection_tx, connection_rx))
+        })
+        .collect()
+}
+
+/// Serves the connections whose request has started to arrive, and queues the others again
+/// behind them, so a client that connects and stalls does not hold up the others.
+fn handle_connections(
+    context: ConnectionHandlerContext,
+    connection_tx: Sender<PendingConnection>,
+    connection_rx: Receiver<PendingConnection>,
+) {
+    while !context.is_cancelled() {
+        let connection = match connection_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(connection) => connection,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        match has_request(&connection.stream) {
+            Ok(true) => accept_connection(connection.stream, &context),
+            Ok(false) if connection.accepted.elapsed() < IDLE_TIMEOUT => {
+                if connection_tx.send(connection).is_err() {
+                    break;
+                }
+            }
+            Ok(false) => warn!("Dropped connection without a request"),
+            Err(e) => warn!("Failed to wait for request: {}", e),
         }
     }
 }
 
+/// Waits briefly for the first byte of the request. A closed connection has nothing to
+/// wait for and is served right away.
+fn has_request(stream: &TcpStream) -> std::io::Result<bool> {
+    stream.set_read_timeout(Some(FIRST_BYTE_WAIT))?;
+    match stream.peek(&mut [0]) {
+        Ok(_) => Ok(true),
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
 #[instrument(name = "Accept connection", skip_all)]
-pub(crate) fn accept_connection(stream: TcpStream, context: AcceptConnectionContext) {
+fn accept_connection(stream: TcpStream, context: &ConnectionHandlerContext) {
     let socket_addr = match stream.peer_addr() {
         Ok(addr) => addr,
         Err(e) => {
@@ -45,22 +94,7 @@ pub(crate) fn accept_connection(stream: TcpStream, context: AcceptConnectionCont
             return;
         }
     };
-
-    let handler_context = ConnectionHandlerContext::new(
-        Arc::clone(&context.cancellation_token),
-        context.udp_socket,
-        context.tickers_router,
-        context.monitoring_router,
-        context.thread_tx.clone(),
-    );
-    let thread = thread::spawn(move || {
-        info!("Accepted connection from {}", socket_addr);
-        let result = handle_connection(stream, handler_context);
-        info!("Connection closed for {}", socket_addr);
-        result
-    });
-    if let Err(e) = context.thread_tx.send(thread) {
-        warn!("Failed to send thread handle: {}", e);
-        context.cancellation_token.cancel();
-    }
+    info!("Accepted connection from {}", socket_addr);
+    handle_connection(stream, context);
+    info!("Finished handling connection from {}", socket_addr);
 }
@@ -1,5 +1,5 @@
 mod monitoring_router;
 mod ping;
 
-pub(crate) use monitoring_router::{MonitoringRouter, MonitoringRouterError};
+pub(crate) use monitoring_router::{MonitoringRouter, MonitoringRouterError, SessionEvent};
 pub(crate) use ping::run_monitoring;
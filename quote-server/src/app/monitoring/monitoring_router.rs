@@ -1,13 +1,30 @@
 use crate::app::client_address::ClientAddress;
 use crossbeam_channel::Sender;
+use quote_streaming::SubscriptionChange;
 use std::collections::HashMap;
 use std::sync::RwLock;
 use tracing::instrument;
 use tracing_log::log::info;
 
+/// Requests from a client to its streaming session.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SessionEvent {
+    Ping,
+    Retransmit {
+        from_seq: u64,
+        to_seq: u64,
+    },
+    /// The first authenticated ping of a session waiting for its return path came from here.
+    Bind(ClientAddress),
+    /// Changes what the session streams.
+    Change(SubscriptionChange),
+}
+
 #[derive(Default)]
 pub(crate) struct MonitoringRouter {
-    monitoring_router: RwLock<HashMap<ClientAddress, Sender<()>>>,
+    monitoring_router: RwLock<HashMap<ClientAddress, Sender<SessionEvent>>>,
+    /// Tokens of the sessions waiting for their first ping, with the address of their route.
+    tokens: RwLock<HashMap<u64, ClientAddress>>,
 }
 
 impl MonitoringRouter {
@@ -19,7 +36,7 @@ impl MonitoringRouter {
     pub(crate) fn add_route(
         &self,
         address: ClientAddress,
-        monitoring_tx: Sender<()>,
+        monitoring_tx: Sender<SessionEvent>,
     ) -> Result<(), MonitoringRouterError> {
         let mut monitoring_router = self
             .monitoring_router
@@ -29,36 +46,136 @@ impl MonitoringRouter {
         Ok(())
     }
 
+    /// Registers the token a session waiting for its return path is bound with.
+    pub(crate) fn add_token(
+        &self,
+        token: u64,
+        address: ClientAddress,
+    ) -> Result<(), MonitoringRouterError> {
+        let mut tokens = self
+            .tokens
+            .write()
+            .map_err(|e| MonitoringRouterError::RwLockPoisoned(e.to_string()))?;
+        tokens.insert(token, address);
+        Ok(())
+    }
+
+    /// Routes the pings from `source` to the session of the token and tells the session to
+    /// stream there. Returns `false` when no session waits for the token.
+    #[instrument(name = "Bind session", skip(self, token), fields(source))]
+    pub(crate) fn bind(
+        &self,
+        token: u64,
+        source: ClientAddress,
+    ) -> Result<bool, MonitoringRouterError> {
+        let Some(address) = self
+            .tokens
+            .write()
+            .map_err(|e| MonitoringRouterError::RwLockPoisoned(e.to_string()))?
+            .remove(&token)
+        else {
+            return Ok(false);
+        };
+
+        let mut monitoring_router = self
+            .monitoring_router
+            .write()
+            .map_err(|e| MonitoringRouterError::RwLockPoisoned(e.to_string()))?;
+        let Some(monitoring_tx) = monitoring_router.get(&address).cloned() else {
+            return Ok(false);
+        };
+        if monitoring_tx
+            .send(SessionEvent::Bind(source.clone()))
+            .is_err()
+        {
+            return Ok(false);
+        }
+        info!("Bound session {} to {}", address, source);
+        monitoring_router.insert(source, monitoring_tx);
+        Ok(true)
+    }
+
     #[instrument(name = "Send ping", skip(self), fields(address))]
     pub(crate) fn send_ping(&self, address: &ClientAddress) -> Result<(), MonitoringRouterError> {
-        let mut is_alive = true;
+        self.send_event(address, SessionEvent::Ping)?;
+        Ok(())
+    }
 
-        {
+    /// Returns `false` when the client has no session.
+    #[instrument(name = "Send retransmit", skip(self), fields(address))]
+    pub(crate) fn send_retransmit(
+        &self,
+        address: &ClientAddress,
+        from_seq: u64,
+        to_seq: u64,
+    ) -> Result<bool, MonitoringRouterError> {
+        self.send_event(address, SessionEvent::Retransmit { from_seq, to_seq })
+    }
+
+    /// Returns `false` when the client has no session.
+    #[instrument(name = "Send subscription change", skip(self), fields(address))]
+    pub(crate) fn send_change(
+        &self,
+        address: &ClientAddress,
+        change: SubscriptionChange,
+    ) -> Result<bool, MonitoringRouterError> {
+        self.send_event(address, SessionEvent::Change(change))
+    }
+
+    fn send_event(
+        &self,
+        address: &ClientAddress,
+        event: SessionEvent,
+    ) -> Result<bool, MonitoringRouterError> {
+        let monitoring_tx = {
             let lock = self
                 .monitoring_router
                 .read()
                 .map_err(|e| MonitoringRouterError::RwLockPoisoned(e.to_string()))?;
-            if let Some(monitoring_tx) = lock.get(address)
-                && monitoring_tx.send(()).is_err()
-            {
-                is_alive = false;
+            match lock.get(address) {
+                Some(monitoring_tx) => monitoring_tx.clone(),
+                None => return Ok(false),
             }
-        }
+        };
 
+        let is_alive = monitoring_tx.send(event).is_ok();
         if !is_alive {
-            self.delete(address)?;
+            self.delete(address, &monitoring_tx)?;
         }
 
-        Ok(())
+        Ok(is_alive)
     }
 
-    #[instrument(name = "Delete monitoring route", skip(self), fields(address))]
-    pub(crate) fn delete(&self, address: &ClientAddress) -> Result<(), MonitoringRouterError> {
+    /// Deletes the route of the session with the given monitoring channel. The route of a
+    /// later session with the same address is kept.
+    #[instrument(
+        name = "Delete monitoring route",
+        skip(self, monitoring_tx),
+        fields(address)
+    )]
+    pub(crate) fn delete(
+        &self,
+        address: &ClientAddress,
+        monitoring_tx: &Sender<SessionEvent>,
+    ) -> Result<(), MonitoringRouterError> {
         let mut lock = self
             .monitoring_router
             .write()
             .map_err(|e| MonitoringRouterError::RwLockPoisoned(e.to_string()))?;
+        if !lock
+            .get(address)
+            .is_some_and(|tx| tx.same_channel(monitoring_tx))
+        {
+            return Ok(());
+        }
         lock.remove(address);
+        drop(lock);
+
+        let mut tokens = self
+            .tokens
+            .write()
+            .map_err(|e| MonitoringRouterError::RwLockPoisoned(e.to_string()))?;
+        tokens.retain(|_, session| session != address);
         info!("Removed monitoring route for address: {}", address);
         Ok(())
     }
@@ -69,3 +186,28 @@ pub(crate) enum MonitoringRouterError {
     #[error("Failed to get lock: {0}")]
     RwLockPoisoned(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn test_bind_routes_pings_from_source() {
+        let router = MonitoringRouter::default();
+        let session = ClientAddress::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 40000);
+        let source = ClientAddress::new(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)), 61000);
+        let (tx, rx) = crossbeam_channel::unbounded();
+        router.add_route(session.clone(), tx).unwrap();
+        router.add_token(42, session.clone()).unwrap();
+
+        assert!(!router.bind(7, source.clone()).unwrap());
+        assert!(router.bind(42, source.clone()).unwrap());
+        assert_eq!(rx.try_recv(), Ok(SessionEvent::Bind(source.clone())));
+        // The token binds only once
+        assert!(!router.bind(42, session.clone()).unwrap());
+
+        router.send_ping(&source).unwrap();
+        assert_eq!(rx.try_recv(), Ok(SessionEvent::Ping));
+    }
+}
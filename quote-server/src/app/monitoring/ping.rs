@@ -1,7 +1,7 @@
 use crate::app::ServerCancellationToken;
 use crate::app::client_address::ClientAddress;
 use crate::app::monitoring::monitoring_router::MonitoringRouter;
-use quote_streaming::{Request, Response};
+use quote_streaming::{Datagram, Request, Response};
 use std::io::ErrorKind;
 use std::net::UdpSocket;
 use std::sync::Arc;
@@ -24,22 +24,23 @@ fn monitoring(
     udp_socket: Arc<UdpSocket>,
     router: Arc<MonitoringRouter>,
 ) {
-    let pong: Vec<u8> = match Response::Pong.try_into() {
-        Ok(bytes) => bytes,
+    let pong = match Vec::<u8>::try_from(Response::Pong) {
+        Ok(bytes) => Datagram::encode(Datagram::UNSEQUENCED, &bytes),
         Err(err) => {
             error!("Failed to serialize pong message: {}", err);
             return;
         }
     };
 
-    let error_response: Vec<u8> =
-        match Response::Error("Invalid request. Expected PING".to_string()).try_into() {
-            Ok(bytes) => bytes,
-            Err(err) => {
-                error!("Failed to serialize error message: {}", err);
-                return;
-            }
-        };
+    let error_response = match Vec::<u8>::try_from(Response::Error(
+        "Invalid request. Expected PING".to_string(),
+    )) {
+        Ok(bytes) => Datagram::encode(Datagram::UNSEQUENCED, &bytes),
+        Err(err) => {
+            error!("Failed to serialize error message: {}", err);
+            return;
+        }
+    };
 
     let mut buffer = [0; 1024];
     loop {
@@ -63,6 +64,55 @@ fn monitoring(
                         warn!("Failed to send ping to monitoring router: {}", e);
                     }
                 }
+                Ok(Request::SessionPing { token }) => {
+                    trace!("Received session ping from {}", address);
+                    if let Err(e) = udp_socket.send_to(&pong, address) {
+                        warn!("Failed to send pong to {}: {}", address, e);
+                        continue;
+                    }
+
+                    // Pings after the first one are routed by the bound source address
+                    let source = ClientAddress::new(address.ip(), address.port());
+                    match router.bind(token, source.clone()) {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            if let Err(e) = router.send_ping(&source) {
+                                warn!("Failed to send ping to monitoring router: {}", e);
+                            }
+                        }
+                        Err(e) => warn!("Failed to bind session to {}: {}", source, e),
+                    }
+                }
+                Ok(Request::Retransmit {
+                    from_seq, to_seq, ..
+                }) => {
+                    trace!(
+                        "Received retransmit {}..={} request from {}",
+                        from_seq, to_seq, address
+                    );
+                    // Resent only to the source of the request, which for a ping source
+                    // session is the source its token was bound to
+                    let source = ClientAddress::new(address.ip(), address.port());
+                    match router.send_retransmit(&source, from_seq, to_seq) {
+                        Ok(true) => {}
+                        Ok(false) => warn!("Ignored retransmit request from {}", source),
+                        Err(e) => warn!("Failed to send retransmit to monitoring router: {}", e),
+                    }
+                }
+                Ok(Request::ChangeSubscription { change, .. }) => {
+                    trace!("Received {:?} from {}", change, address);
+                    // Only the session's own address may change it, which for a ping source
+                    // session is the source its token was bound to
+                    let source = ClientAddress::new(address.ip(), address.port());
+                    match router.send_change(&source, change) {
+                        Ok(true) => {}
+                        Ok(false) => warn!("Ignored subscription change from {}", source),
+                        Err(e) => warn!(
+                            "Failed to send subscription change to monitoring router: {}",
+                            e
+                        ),
+                    }
+                }
                 Ok(_) => {
                     warn!("Received invalid request from {}", address);
                     if let Err(e) = udp_socket.send_to(&error_response, address) {
@@ -1,7 +1,36 @@
+mod conflation;
+#[cfg(feature = "bench")]
+mod fan_out;
+mod multicast;
+mod quote_filters;
 mod quotes_generator;
+mod rate_limit;
+mod session_queue;
+mod shared_quote;
 mod stream_quotes;
+mod streaming_config;
+mod tcp_transport;
+mod ticker_groups;
+mod ticker_pattern;
+mod tickers_reloader;
 mod tickers_router;
+mod udp_sender;
 
-pub(crate) use quotes_generator::run_quotes_generator;
-pub(crate) use stream_quotes::{StreamQuotesContext, stream_quotes};
-pub(crate) use tickers_router::{TickersRouter, TickersRouterError};
+#[cfg(feature = "bench")]
+pub use fan_out::FanOut;
+pub use multicast::{MulticastConfig, MulticastError};
+pub(crate) use multicast::{MulticastGroups, run_multicast_publisher};
+pub(crate) use quotes_generator::{run_quote_source, run_quotes_generator};
+pub use session_queue::OverflowPolicy;
+pub(crate) use session_queue::{OverflowCounters, QuoteSendError, QuoteSender, SessionQueue};
+pub(crate) use shared_quote::SharedQuote;
+pub(crate) use stream_quotes::{
+    Session, StreamQuotesContext, StreamQuotesPool, StreamQuotesPoolError, run_stream_quotes,
+};
+pub(crate) use streaming_config::StreamingConfig;
+pub use ticker_groups::read_ticker_groups;
+pub(crate) use ticker_pattern::TickerPattern;
+pub use tickers_reloader::read_tickers;
+pub(crate) use tickers_reloader::run_tickers_reloader;
+pub(crate) use tickers_router::TickersRouter;
+pub use tickers_router::TickersRouterError;
@@ -0,0 +1,95 @@
+use crate::app::quote_streaming::SharedQuote;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub(crate) struct Conflation {
+    interval: Duration,
+    pending: HashMap<String, SharedQuote>,
+    last_sent: HashMap<String, Instant>,
+}
+
+impl Conflation {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            pending: HashMap::new(),
+            last_sent: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub(crate) fn offer(&mut self, quote: SharedQuote, now: Instant) -> Option<SharedQuote> {
+        match self.last_sent.get_mut(quote.ticker()) {
+            Some(last_sent) if now.duration_since(*last_sent) < self.interval => {
+                self.pending.insert(quote.ticker().to_string(), quote);
+                None
+            }
+            Some(last_sent) => {
+                *last_sent = now;
+                Some(quote)
+            }
+            None => {
+                self.last_sent.insert(quote.ticker().to_string(), now);
+                Some(quote)
+            }
+        }
+    }
+
+    pub(crate) fn has_due(&self, now: Instant) -> bool {
+        self.pending.keys().any(|ticker| self.is_due(ticker, now))
+    }
+
+    pub(crate) fn take_due(&mut self, now: Instant, limit: usize) -> Vec<SharedQuote> {
+        let due: Vec<String> = self
+            .pending
+            .keys()
+            .filter(|ticker| self.is_due(ticker, now))
+            .take(limit)
+            .cloned()
+            .collect();
+
+        due.into_iter()
+            .filter_map(|ticker| {
+                self.last_sent.insert(ticker.clone(), now);
+                self.pending.remove(&ticker)
+            })
+            .collect()
+    }
+
+    fn is_due(&self, ticker: &str, now: Instant) -> bool {
+        self.last_sent
+            .get(ticker)
+            .is_none_or(|last_sent| now.duration_since(*last_sent) >= self.interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote_streaming::StockQuote;
+
+    fn quote(ticker: &str) -> SharedQuote {
+        SharedQuote::new(StockQuote::generate(ticker)).unwrap()
+    }
+
+    #[test]
+    fn test_conflation_sends_latest_once_per_interval() {
+        let start = Instant::now();
+        let mut conflation = Conflation::new(Duration::from_secs(1));
+
+        assert!(conflation.offer(quote("AAPL"), start).is_some());
+        assert!(conflation.offer(quote("MSFT"), start).is_some());
+        let latest = quote("AAPL");
+        assert!(conflation.offer(quote("AAPL"), start).is_none());
+        assert!(conflation.offer(latest.clone(), start).is_none());
+        assert!(!conflation.has_due(start));
+
+        let due = conflation.take_due(start + Duration::from_secs(1), usize::MAX);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].bytes(), latest.bytes());
+        assert!(!conflation.has_due(start + Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::app::client_address::ClientAddress;
+use crate::app::quote_streaming::{
+    OverflowPolicy, SessionQueue, SharedQuote, TickersRouter, TickersRouterError,
+};
+use crossbeam_channel::Receiver;
+use quote_streaming::StockQuote;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+/// The tickers router of the server with in-memory sessions, driven by the fan-out benchmark.
+pub struct FanOut {
+    router: TickersRouter,
+    queues: Vec<Receiver<SharedQuote>>,
+}
+
+impl FanOut {
+    /// Routes the ticker to `subscribers` sessions queueing up to `queue_capacity` quotes.
+    pub fn new(
+        ticker: &str,
+        subscribers: u32,
+        queue_capacity: usize,
+    ) -> Result<Self, TickersRouterError> {
+        let router = TickersRouter::new(vec![ticker.to_string()], Vec::new());
+        let queue = SessionQueue::new(queue_capacity, OverflowPolicy::DropOldest, Arc::default());
+        let mut queues = Vec::new();
+        for subscriber in 0..subscribers {
+            let (tx, rx) = queue.channel();
+            let address = IpAddr::V4(Ipv4Addr::from(
+                u32::from(Ipv4Addr::new(10, 0, 0, 0)) + subscriber,
+            ));
+            router.add_routes(
+                vec![ticker.to_string()],
+                tx,
+                ClientAddress::new(address, 5153),
+            )?;
+            queues.push(rx);
+        }
+        Ok(Self { router, queues })
+    }
+
+    /// Routes the quote to the sessions, as the quotes generator does.
+    pub fn send_quote(&self, quote: StockQuote) -> Result<(), TickersRouterError> {
+        self.router.send_quote(quote)
+    }
+
+    /// Takes the queued quotes of every session, as the stream workers do, and returns the
+    /// number of bytes their datagrams carry.
+    pub fn drain(&self) -> usize {
+        self.queues
+            .iter()
+            .flat_map(|rx| rx.try_iter())
+            .map(|quote| size_of::<u64>() + quote.bytes().len())
+            .sum()
+    }
+}
@@ -0,0 +1,201 @@
+use crate::app::quote_streaming::SharedQuote;
+use crate::app::quote_streaming::udp_sender::send_datagram;
+use crate::app::server_cancellation_token::ServerCancellationToken;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use quote_streaming::{Datagram, MulticastGroup};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{info, instrument, trace, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Multicast groups the server publishes every quote to.
+#[derive(Debug, Clone)]
+pub struct MulticastConfig {
+    address: Ipv4Addr,
+    port: u16,
+    groups: u8,
+}
+
+impl MulticastConfig {
+    /// Spreads the tickers over `groups` consecutive group addresses starting at `address`.
+    pub fn new(address: Ipv4Addr, port: u16, groups: u8) -> Self {
+        Self {
+            address,
+            port,
+            groups,
+        }
+    }
+}
+
+pub(crate) struct MulticastGroups {
+    port: u16,
+    groups: Vec<Ipv4Addr>,
+    ticker_groups: RwLock<HashMap<String, usize>>,
+}
+
+impl MulticastGroups {
+    pub(crate) fn new(
+        config: &MulticastConfig,
+        tickers: &[String],
+    ) -> Result<Self, MulticastError> {
+        if config.groups == 0 {
+            return Err(MulticastError::NoGroups);
+        }
+
+        let base = u32::from(config.address);
+        let mut groups = Vec::with_capacity(config.groups as usize);
+        for i in 0..config.groups {
+            let address = base
+                .checked_add(i as u32)
+                .map(Ipv4Addr::from)
+                .filter(Ipv4Addr::is_multicast)
+                .ok_or(MulticastError::NotMulticast(config.address, config.groups))?;
+            groups.push(address);
+        }
+
+        let ticker_groups = tickers
+            .iter()
+            .enumerate()
+            .map(|(i, ticker)| (ticker.clone(), i % groups.len()))
+            .collect();
+
+        Ok(Self {
+            port: config.port,
+            groups,
+            ticker_groups: RwLock::new(ticker_groups),
+        })
+    }
+
+    /// Assigns groups to tickers added by a reload, continuing the round-robin order.
+    pub(crate) fn add_tickers(&self, tickers: &[String]) {
+        let mut ticker_groups = self
+            .ticker_groups
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        for ticker in tickers {
+            let group = ticker_groups.len() % self.groups.len();
+            ticker_groups.entry(ticker.clone()).or_insert(group);
+        }
+    }
+
+    pub(crate) fn groups_for(&self, tickers: &[String]) -> Vec<MulticastGroup> {
+        let ticker_groups = self
+            .ticker_groups
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut group_tickers = vec![Vec::new(); self.groups.len()];
+        for ticker in tickers {
+            if let Some(&group) = ticker_groups.get(ticker) {
+                group_tickers[group].push(ticker.clone());
+            }
+        }
+
+        group_tickers
+            .into_iter()
+            .enumerate()
+            .filter(|(_, tickers)| !tickers.is_empty())
+            .map(|(i, tickers)| MulticastGroup::new(IpAddr::V4(self.groups[i]), self.port, tickers))
+            .collect()
+    }
+
+    fn destination(&self, ticker: &str) -> Option<SocketAddr> {
+        self.ticker_groups
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(ticker)
+            .map(|&group| SocketAddr::from((self.groups[group], self.port)))
+    }
+}
+
+#[instrument(name = "Run multicast publisher", skip_all)]
+pub(crate) fn run_multicast_publisher(
+    cancellation_token: Arc<ServerCancellationToken>,
+    udp_socket: UdpSocket,
+    groups: Arc<MulticastGroups>,
+    quote_rx: Receiver<SharedQuote>,
+) -> JoinHandle<()> {
+    thread::spawn(move || publish(cancellation_token, udp_socket, groups, quote_rx))
+}
+
+#[instrument(name = "Publish multicast quotes", skip_all)]
+fn publish(
+    cancellation_token: Arc<ServerCancellationToken>,
+    udp_socket: UdpSocket,
+    groups: Arc<MulticastGroups>,
+    quote_rx: Receiver<SharedQuote>,
+) {
+    for group in groups.groups.iter() {
+        info!(
+            "Publishing quotes to multicast group {}:{}",
+            group, groups.port
+        );
+    }
+
+    while !cancellation_token.is_cancelled() {
+        let quote = match quote_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(quote) => quote,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => {
+                warn!("Quotes receiver disconnected");
+                break;
+            }
+        };
+
+        let Some(destination) = groups.destination(quote.ticker()) else {
+            trace!("Ticker {} has no multicast group", quote.ticker());
+            continue;
+        };
+        if let Err(e) = send_datagram(
+            &udp_socket,
+            Datagram::UNSEQUENCED,
+            quote.bytes(),
+            destination,
+        ) {
+            warn!("Failed to publish quote to {}: {}", destination, e);
+        }
+    }
+}
+
+/// Invalid multicast configurations.
+#[derive(Debug, thiserror::Error)]
+pub enum MulticastError {
+    /// The configuration has no groups.
+    #[error("At least one multicast group is required")]
+    NoGroups,
+    /// Some of the group addresses are outside the multicast range.
+    #[error("{1} groups starting at {0} are not all multicast addresses")]
+    NotMulticast(Ipv4Addr, u8),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_for_tickers() {
+        let config = MulticastConfig::new(Ipv4Addr::new(239, 255, 0, 1), 5154, 2);
+        let tickers = ["AAPL", "MSFT", "NVDA"].map(String::from);
+        let groups = MulticastGroups::new(&config, &tickers).unwrap();
+
+        let result = groups.groups_for(&["NVDA".to_string(), "MSFT".to_string()]);
+
+        assert_eq!(
+            result,
+            vec![
+                MulticastGroup::new("239.255.0.1".parse().unwrap(), 5154, vec!["NVDA".into()]),
+                MulticastGroup::new("239.255.0.2".parse().unwrap(), 5154, vec!["MSFT".into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_groups_must_be_multicast() {
+        let config = MulticastConfig::new(Ipv4Addr::new(239, 255, 255, 255), 5154, 2);
+        assert!(MulticastGroups::new(&config, &[]).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use quote_streaming::{QuoteFilter, StockQuote};
+use std::collections::HashMap;
+
+struct FilterState {
+    filter: QuoteFilter,
+    last_sent_price: Option<i64>,
+}
+
+pub(crate) struct QuoteFilters {
+    filters: HashMap<String, FilterState>,
+}
+
+impl QuoteFilters {
+    pub(crate) fn new(filters: &[QuoteFilter]) -> Self {
+        let filters = filters
+            .iter()
+            .map(|filter| {
+                let state = FilterState {
+                    filter: filter.clone(),
+                    last_sent_price: None,
+                };
+                (filter.ticker().to_string(), state)
+            })
+            .collect();
+        Self { filters }
+    }
+
+    pub(crate) fn matches(&self, quote: &StockQuote) -> bool {
+        self.filters
+            .get(quote.ticker())
+            .is_none_or(|state| state.filter.matches(quote, state.last_sent_price))
+    }
+
+    pub(crate) fn sent(&mut self, quote: &StockQuote) {
+        if let Some(state) = self.filters.get_mut(quote.ticker()) {
+            state.last_sent_price = Some(quote.price());
+        }
+    }
+}
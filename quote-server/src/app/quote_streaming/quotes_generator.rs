@@ -1,24 +1,24 @@
 use crate::app::quote_streaming::tickers_router::TickersRouter;
 use crate::app::server_cancellation_token::ServerCancellationToken;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
 use quote_streaming::StockQuote;
 use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
 use std::time::Duration;
 use tracing::warn;
-use tracing::{instrument, trace};
+use tracing::{info, instrument, trace};
 
 const DEFAULT_GENERATION_INTERVAL: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 #[instrument(name = "Run quotes generator", skip_all)]
 pub(crate) fn run_quotes_generator(
-    tickers: Vec<String>,
     tickers_router: Arc<TickersRouter>,
     cancellation_token: Arc<ServerCancellationToken>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         quotes_generator(
-            tickers,
             cancellation_token,
             tickers_router,
             DEFAULT_GENERATION_INTERVAL,
@@ -28,12 +28,18 @@ pub(crate) fn run_quotes_generator(
 
 #[instrument(name = "Generate quotes", skip_all)]
 fn quotes_generator(
-    tickers: Vec<String>,
     cancellation_token: Arc<ServerCancellationToken>,
     tickers_router: Arc<TickersRouter>,
     interval: Duration,
 ) {
     while !cancellation_token.is_cancelled() {
+        let tickers = match tickers_router.tickers() {
+            Ok(tickers) => tickers,
+            Err(e) => {
+                warn!("Failed to get tickers: {e}");
+                return;
+            }
+        };
         for ticker in tickers.iter() {
             if cancellation_token.is_cancelled() {
                 return;
@@ -49,3 +55,36 @@ fn quotes_generator(
         thread::sleep(interval);
     }
 }
+
+/// Routes the quotes of an embedding program instead of generating them.
+#[instrument(name = "Run quote source", skip_all)]
+pub(crate) fn run_quote_source(
+    tickers_router: Arc<TickersRouter>,
+    cancellation_token: Arc<ServerCancellationToken>,
+    quote_rx: Receiver<StockQuote>,
+) -> JoinHandle<()> {
+    thread::spawn(move || route_quotes(cancellation_token, tickers_router, quote_rx))
+}
+
+#[instrument(name = "Route source quotes", skip_all)]
+fn route_quotes(
+    cancellation_token: Arc<ServerCancellationToken>,
+    tickers_router: Arc<TickersRouter>,
+    quote_rx: Receiver<StockQuote>,
+) {
+    while !cancellation_token.is_cancelled() {
+        let quote = match quote_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(quote) => quote,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => {
+                info!("Quote source disconnected");
+                break;
+            }
+        };
+        let ticker = quote.ticker().to_string();
+        match tickers_router.send_quote(quote) {
+            Ok(_) => trace!("Quote {} was received and sent", ticker),
+            Err(e) => warn!("Failed to send quotes: {e}"),
+        }
+    }
+}
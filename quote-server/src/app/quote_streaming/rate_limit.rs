@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+
+const NOTICE_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(crate) struct RateLimit {
+    rate: u32,
+    tokens: f64,
+    last_refill: Instant,
+    last_notice: Option<Instant>,
+}
+
+impl RateLimit {
+    pub(crate) fn new(rate: u32, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate as f64,
+            last_refill: now,
+            last_notice: None,
+        }
+    }
+
+    pub(crate) fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub(crate) fn available(&mut self, now: Instant) -> usize {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.rate as f64);
+        self.last_refill = now;
+        self.tokens as usize
+    }
+
+    pub(crate) fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+
+    pub(crate) fn should_notify(&mut self, now: Instant) -> bool {
+        if self
+            .last_notice
+            .is_some_and(|last_notice| now.duration_since(last_notice) < NOTICE_INTERVAL)
+        {
+            return false;
+        }
+        self.last_notice = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut rate_limit = RateLimit::new(2, start);
+
+        assert_eq!(rate_limit.available(start), 2);
+        rate_limit.take();
+        rate_limit.take();
+        assert_eq!(rate_limit.available(start), 0);
+        assert_eq!(rate_limit.available(start + Duration::from_millis(400)), 0);
+        assert_eq!(rate_limit.available(start + Duration::from_millis(500)), 1);
+        assert_eq!(rate_limit.available(start + Duration::from_secs(10)), 2);
+    }
+}
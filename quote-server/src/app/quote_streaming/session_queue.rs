@@ -0,0 +1,205 @@
+use crate::app::quote_streaming::SharedQuote;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// What happens to a quote for a session whose queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drops the oldest queued quote.
+    DropOldest,
+    /// Replaces the queued quote of the same ticker, or drops the oldest one.
+    Conflate,
+    /// Ends the session.
+    Disconnect,
+}
+
+#[derive(Default, Debug)]
+pub(crate) struct OverflowCounters {
+    dropped: AtomicU64,
+    conflated: AtomicU64,
+    disconnected: AtomicU64,
+}
+
+impl OverflowCounters {
+    pub(crate) fn snapshot(&self) -> (u64, u64, u64) {
+        (
+            self.dropped.load(Ordering::Relaxed),
+            self.conflated.load(Ordering::Relaxed),
+            self.disconnected.load(Ordering::Relaxed),
+        )
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SessionQueue {
+    capacity: usize,
+    policy: OverflowPolicy,
+    counters: Arc<OverflowCounters>,
+}
+
+impl SessionQueue {
+    pub(crate) fn new(
+        capacity: usize,
+        policy: OverflowPolicy,
+        counters: Arc<OverflowCounters>,
+    ) -> Self {
+        Self {
+            capacity,
+            policy,
+            counters,
+        }
+    }
+
+    pub(crate) fn channel(&self) -> (QuoteSender, Receiver<SharedQuote>) {
+        let (tx, rx) = crossbeam_channel::bounded(self.capacity);
+        let sender = QuoteSender {
+            tx,
+            rx: rx.clone(),
+            queue: self.clone(),
+        };
+        (sender, rx)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct QuoteSender {
+    tx: Sender<SharedQuote>,
+    rx: Receiver<SharedQuote>,
+    queue: SessionQueue,
+}
+
+impl QuoteSender {
+    /// The queue the quotes go to, which identifies the session reading it.
+    pub(crate) fn queue(&self) -> &Receiver<SharedQuote> {
+        &self.rx
+    }
+
+    pub(crate) fn send(&self, quote: SharedQuote) -> Result<(), QuoteSendError> {
+        let quote = match self.tx.try_send(quote) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Full(quote)) => quote,
+            Err(TrySendError::Disconnected(_)) => return Err(QuoteSendError::Disconnected),
+        };
+
+        match self.queue.policy {
+            OverflowPolicy::DropOldest => self.drop_oldest(quote),
+            OverflowPolicy::Conflate => self.conflate(quote),
+            OverflowPolicy::Disconnect => {
+                self.queue
+                    .counters
+                    .disconnected
+                    .fetch_add(1, Ordering::Relaxed);
+                Err(QuoteSendError::Overflow)
+            }
+        }
+    }
+
+    fn drop_oldest(&self, mut quote: SharedQuote) -> Result<(), QuoteSendError> {
+        loop {
+            if self.rx.try_recv().is_ok() {
+                self.queue.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            match self.tx.try_send(quote) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Full(rejected)) => quote = rejected,
+                Err(TrySendError::Disconnected(_)) => return Err(QuoteSendError::Disconnected),
+            }
+        }
+    }
+
+    fn conflate(&self, quote: SharedQuote) -> Result<(), QuoteSendError> {
+        let mut queued: Vec<SharedQuote> = self.rx.try_iter().collect();
+        queued.push(quote);
+
+        // Walked from the newest quote, so the first one seen of each ticker is kept
+        let mut seen = HashSet::with_capacity(queued.len());
+        let mut latest: Vec<SharedQuote> = queued
+            .iter()
+            .rev()
+            .filter(|quote| seen.insert(quote.ticker()))
+            .cloned()
+            .collect();
+        latest.reverse();
+        self.queue
+            .counters
+            .conflated
+            .fetch_add((queued.len() - latest.len()) as u64, Ordering::Relaxed);
+
+        let mut latest = latest.into_iter();
+        for quote in latest.by_ref() {
+            match self.tx.try_send(quote) {
+                Ok(()) => {}
+                Err(TrySendError::Full(quote)) => return self.drop_oldest(quote),
+                Err(TrySendError::Disconnected(_)) => return Err(QuoteSendError::Disconnected),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum QuoteSendError {
+    #[error("Session queue is disconnected")]
+    Disconnected,
+    #[error("Session queue is full")]
+    Overflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote_streaming::StockQuote;
+
+    fn quote(ticker: &str) -> SharedQuote {
+        SharedQuote::new(StockQuote::generate(ticker)).unwrap()
+    }
+
+    fn tickers(rx: &Receiver<SharedQuote>) -> Vec<String> {
+        rx.try_iter().map(|q| q.ticker().to_string()).collect()
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let counters = Arc::new(OverflowCounters::default());
+        let queue = SessionQueue::new(2, OverflowPolicy::DropOldest, Arc::clone(&counters));
+        let (tx, rx) = queue.channel();
+
+        for ticker in ["AAPL", "MSFT", "NVDA"] {
+            tx.send(quote(ticker)).unwrap();
+        }
+
+        assert_eq!(tickers(&rx), ["MSFT", "NVDA"]);
+        assert_eq!(counters.snapshot(), (1, 0, 0));
+    }
+
+    #[test]
+    fn test_conflate() {
+        let counters = Arc::new(OverflowCounters::default());
+        let queue = SessionQueue::new(3, OverflowPolicy::Conflate, Arc::clone(&counters));
+        let (tx, rx) = queue.channel();
+
+        for ticker in ["AAPL", "MSFT", "AAPL", "NVDA"] {
+            tx.send(quote(ticker)).unwrap();
+        }
+
+        assert_eq!(tickers(&rx), ["MSFT", "AAPL", "NVDA"]);
+        assert_eq!(counters.snapshot(), (0, 1, 0));
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let counters = Arc::new(OverflowCounters::default());
+        let queue = SessionQueue::new(1, OverflowPolicy::Disconnect, Arc::clone(&counters));
+        let (tx, _rx) = queue.channel();
+
+        tx.send(quote("AAPL")).unwrap();
+
+        assert!(matches!(
+            tx.send(quote("MSFT")),
+            Err(QuoteSendError::Overflow)
+        ));
+        assert_eq!(counters.snapshot(), (0, 0, 1));
+    }
+}
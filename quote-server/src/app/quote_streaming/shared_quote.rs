@@ -0,0 +1,39 @@
+use quote_streaming::{Response, StockQuote};
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+pub(crate) struct SharedQuote {
+    quote: Arc<StockQuote>,
+    bytes: Arc<[u8]>,
+}
+
+impl SharedQuote {
+    pub(crate) fn new(quote: StockQuote) -> Result<Self, rancor::Error> {
+        let bytes: Vec<u8> = Response::Quote(quote.clone()).try_into()?;
+        Ok(Self {
+            quote: Arc::new(quote),
+            bytes: bytes.into(),
+        })
+    }
+
+    /// The same quote flagged as a snapshot of the last known value.
+    pub(crate) fn snapshot(&self) -> Result<Self, rancor::Error> {
+        let bytes: Vec<u8> = Response::SnapshotQuote(self.quote().clone()).try_into()?;
+        Ok(Self {
+            quote: Arc::clone(&self.quote),
+            bytes: bytes.into(),
+        })
+    }
+
+    pub(crate) fn quote(&self) -> &StockQuote {
+        &self.quote
+    }
+
+    pub(crate) fn ticker(&self) -> &str {
+        self.quote.ticker()
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
@@ -1,89 +1,551 @@
 use crate::app::ServerCancellationToken;
 use crate::app::client_address::ClientAddress;
-use crossbeam_channel::{Receiver, select_biased};
-use quote_streaming::{Response, StockQuote};
-use std::net::UdpSocket;
+use crate::app::monitoring::{MonitoringRouter, SessionEvent};
+use crate::app::quote_streaming::conflation::Conflation;
+use crate::app::quote_streaming::quote_filters::QuoteFilters;
+use crate::app::quote_streaming::rate_limit::RateLimit;
+use crate::app::quote_streaming::tcp_transport::TcpTransport;
+use crate::app::quote_streaming::udp_sender::send_datagram;
+use crate::app::quote_streaming::{SharedQuote, TickersRouter};
+use crossbeam_channel::{Receiver, Select, Sender, TryRecvError};
+use quote_streaming::{Datagram, QuoteFilter, Request, Response, SubscriptionChange};
+use std::collections::VecDeque;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::{TcpStream, UdpSocket};
 use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 use tracing::{info, instrument, trace, warn};
 
 const TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const RETRANSMIT_CAPACITY: usize = 1024;
 
 pub(crate) struct StreamQuotesContext {
     cancellation_token: Arc<ServerCancellationToken>,
     udp_socket: Arc<UdpSocket>,
-    quote_rx: Receiver<StockQuote>,
-    monitoring_rx: Receiver<()>,
-    address: ClientAddress,
+    tickers_router: Arc<TickersRouter>,
+    monitoring_router: Arc<MonitoringRouter>,
 }
 
 impl StreamQuotesContext {
     pub(crate) fn new(
         cancellation_token: Arc<ServerCancellationToken>,
         udp_socket: Arc<UdpSocket>,
-        quote_rx: Receiver<StockQuote>,
-        monitoring_rx: Receiver<()>,
-        address: ClientAddress,
+        tickers_router: Arc<TickersRouter>,
+        monitoring_router: Arc<MonitoringRouter>,
     ) -> Self {
         Self {
             cancellation_token,
             udp_socket,
+            tickers_router,
+            monitoring_router,
+        }
+    }
+}
+
+pub(crate) struct Session {
+    /// The address the session's routes are registered with.
+    address: ClientAddress,
+    /// Where UDP datagrams go; unknown until the first ping of a ping source session.
+    destination: Option<ClientAddress>,
+    quote_rx: Receiver<SharedQuote>,
+    monitoring_rx: Receiver<SessionEvent>,
+    /// The sender of the session's monitoring route, which tells its route apart from the
+    /// route of a later session with the same address.
+    monitoring_tx: Sender<SessionEvent>,
+    last_ping_time: Instant,
+    conflation: Option<Conflation>,
+    rate_limit: Option<RateLimit>,
+    filters: QuoteFilters,
+    next_sequence: u64,
+    /// The last sent quotes with their sequence numbers, kept for retransmission.
+    sent: VecDeque<(u64, SharedQuote)>,
+    /// The connection of a session streaming over TCP instead of UDP.
+    tcp: Option<TcpTransport>,
+    /// Set while the client has paused the stream; quotes are dropped meanwhile.
+    paused: bool,
+}
+
+impl Session {
+    pub(crate) fn new(
+        address: ClientAddress,
+        quote_rx: Receiver<SharedQuote>,
+        monitoring_tx: Sender<SessionEvent>,
+        monitoring_rx: Receiver<SessionEvent>,
+        conflation_interval: Option<Duration>,
+        max_rate: Option<u32>,
+        filters: &[QuoteFilter],
+    ) -> Self {
+        let now = Instant::now();
+        Self {
+            destination: Some(address.clone()),
+            address,
             quote_rx,
             monitoring_rx,
-            address,
+            monitoring_tx,
+            last_ping_time: now,
+            conflation: conflation_interval.map(Conflation::new),
+            rate_limit: max_rate.map(|rate| RateLimit::new(rate, now)),
+            filters: QuoteFilters::new(filters),
+            next_sequence: 1,
+            sent: VecDeque::with_capacity(RETRANSMIT_CAPACITY),
+            tcp: None,
+            paused: false,
         }
     }
-}
 
-#[instrument(name = "Stream quotes", skip_all)]
-pub(crate) fn stream_quotes(context: StreamQuotesContext) -> Option<ClientAddress> {
-    let mut last_ping_time = Instant::now();
+    /// Holds the quotes back until the first authenticated ping tells where to send them.
+    pub(crate) fn awaiting_ping(mut self) -> Self {
+        self.destination = None;
+        self
+    }
 
-    while !context.cancellation_token.is_cancelled() {
-        select_biased! {
-            recv(context.monitoring_rx) -> msg => {
-                match msg {
-                    Ok(_) => {
-                        last_ping_time = Instant::now();
+    /// Streams the quotes over the connection instead of UDP.
+    pub(crate) fn with_tcp_stream(mut self, stream: TcpStream) -> std::io::Result<Self> {
+        self.tcp = Some(TcpTransport::new(stream)?);
+        Ok(self)
+    }
+
+    fn receive_events(&mut self, context: &StreamQuotesContext) -> Result<(), SessionClosed> {
+        self.receive_heartbeats(context)?;
+        loop {
+            match self.monitoring_rx.try_recv() {
+                Ok(SessionEvent::Ping) => self.last_ping_time = Instant::now(),
+                Ok(SessionEvent::Retransmit { from_seq, to_seq }) => {
+                    self.retransmit(context, from_seq, to_seq)?
+                }
+                Ok(SessionEvent::Bind(destination)) => {
+                    info!("Streaming quotes for {} to {}", self.address, destination);
+                    self.last_ping_time = Instant::now();
+                    self.destination = Some(destination);
+                }
+                Ok(SessionEvent::Change(change)) => self.change_subscription(context, change)?,
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => {
+                    warn!("Monitoring receiver disconnected for {}", self.address);
+                    return Err(SessionClosed);
+                }
+            }
+        }
+    }
+
+    /// Answers the pings a TCP session sends over its connection, and applies the
+    /// subscription changes sent along with them.
+    fn receive_heartbeats(&mut self, context: &StreamQuotesContext) -> Result<(), SessionClosed> {
+        let Some(tcp) = self.tcp.as_mut() else {
+            return Ok(());
+        };
+        let frames = tcp.receive().map_err(|e| {
+            info!("Connection of {} closed: {}", self.address, e);
+            SessionClosed
+        })?;
+
+        for frame in frames {
+            match Request::try_from(frame.as_slice()) {
+                Ok(Request::Ping) => {
+                    self.last_ping_time = Instant::now();
+                    match Vec::<u8>::try_from(Response::Pong) {
+                        Ok(pong) => {
+                            self.send_datagram(&context.udp_socket, Datagram::UNSEQUENCED, &pong)?
+                        }
+                        Err(e) => warn!("Failed to serialize pong: {}", e),
                     }
-                    Err(_) => {
-                        warn!("Monitoring receiver disconnected");
-                        break;
+                }
+                Ok(Request::ChangeSubscription { change, .. }) => {
+                    self.change_subscription(context, change)?
+                }
+                Ok(request) => warn!("Unexpected request from {}: {:?}", self.address, request),
+                Err(e) => {
+                    warn!("Invalid request from {}: {}", self.address, e);
+                    return Err(SessionClosed);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[instrument(name = "Change subscription", skip(self, context), fields(address = %self.address))]
+    fn change_subscription(
+        &mut self,
+        context: &StreamQuotesContext,
+        change: SubscriptionChange,
+    ) -> Result<(), SessionClosed> {
+        info!("Changing subscription of {}: {:?}", self.address, change);
+        let result = match change {
+            SubscriptionChange::Add(tickers) => context
+                .tickers_router
+                .add_client_tickers(tickers, &self.address)
+                .map(|_| ()),
+            SubscriptionChange::Remove(tickers) => context
+                .tickers_router
+                .remove_client_tickers(&tickers, &self.address),
+            SubscriptionChange::Pause => {
+                self.paused = true;
+                // Quotes held back for conflation would be stale after resuming
+                if let Some(conflation) = self.conflation.as_mut() {
+                    *conflation = Conflation::new(conflation.interval());
+                }
+                Ok(())
+            }
+            SubscriptionChange::Resume if self.paused => {
+                self.paused = false;
+                match context.tickers_router.client_snapshot(&self.address) {
+                    Ok(snapshot) => {
+                        for quote in snapshot {
+                            self.send_sequenced(&context.udp_socket, quote)?;
+                        }
+                        Ok(())
                     }
+                    Err(e) => Err(e),
                 }
             }
-            recv(context.quote_rx) -> msg => {
-                match msg {
-                    Ok(quote) => {
-                        trace!("Received quotes {}", quote.ticker());
-                        let response: Result<Vec<u8>,_> = Response::Quote(quote.clone()).try_into();
-                        let Ok(quotes_bytes) = response else {
-                                warn!("Failed to serialize quote: {:?}", quote);
-                                context.cancellation_token.cancel();
-                                break;
-                            };
-                            if let Err(e) = context.udp_socket.send_to(&quotes_bytes, context.address.address()) {
-                                warn!("Failed to send quote: {}", e);
-                                break;
-                            };
-                    },
-                    Err(_) => {
-                        warn!("Quotes receiver disconnected");
-                        break;
+            SubscriptionChange::Resume => Ok(()),
+        };
+        if let Err(e) = result {
+            warn!("Failed to change subscription of {}: {}", self.address, e);
+        }
+        Ok(())
+    }
+
+    fn available(&mut self, now: Instant) -> usize {
+        // A paused session drops its quotes as fast as they come
+        if self.paused {
+            return usize::MAX;
+        }
+        if self.tcp.as_ref().is_some_and(TcpTransport::is_congested)
+            || (self.tcp.is_none() && self.destination.is_none())
+        {
+            return 0;
+        }
+        self.rate_limit
+            .as_mut()
+            .map_or(usize::MAX, |rate_limit| rate_limit.available(now))
+    }
+
+    fn send_quotes(&mut self, udp_socket: &UdpSocket) -> Result<(), SessionClosed> {
+        if let Some(tcp) = self.tcp.as_mut() {
+            tcp.flush().map_err(|e| {
+                warn!("Failed to send quotes to {}: {}", self.address, e);
+                SessionClosed
+            })?;
+        }
+
+        if self.paused {
+            loop {
+                match self.quote_rx.try_recv() {
+                    Ok(_) => {}
+                    Err(TryRecvError::Empty) => return Ok(()),
+                    Err(TryRecvError::Disconnected) => {
+                        warn!("Quotes receiver disconnected for {}", self.address);
+                        return Err(SessionClosed);
                     }
                 }
             }
-            default(Duration::from_millis(100)) => {
-                if last_ping_time.elapsed() > TIMEOUT {
-                    info!(
-                        "Last ping for {} was more than 5 seconds ago, stop streaming quotes",
-                        context.address
-                    );
-                    break;
+        }
+
+        let now = Instant::now();
+        while self.available(now) > 0 {
+            match self.quote_rx.try_recv() {
+                Ok(quote) => {
+                    trace!("Received quotes {}", quote.ticker());
+                    if !self.filters.matches(quote.quote()) {
+                        continue;
+                    }
+                    let quote = match self.conflation.as_mut() {
+                        Some(conflation) => conflation.offer(quote, now),
+                        None => Some(quote),
+                    };
+                    if let Some(quote) = quote {
+                        self.send(udp_socket, &quote)?;
+                    }
                 }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    warn!("Quotes receiver disconnected for {}", self.address);
+                    return Err(SessionClosed);
+                }
+            }
+        }
+
+        let available = self.available(now);
+        if let Some(conflation) = self.conflation.as_mut() {
+            for quote in conflation.take_due(now, available) {
+                self.send(udp_socket, &quote)?;
             }
         }
+
+        if self.is_throttled(now) {
+            self.notify_throttled(udp_socket, now)?;
+        }
+        Ok(())
     }
 
-    Some(context.address)
+    fn send(&mut self, udp_socket: &UdpSocket, quote: &SharedQuote) -> Result<(), SessionClosed> {
+        if let Some(rate_limit) = self.rate_limit.as_mut() {
+            rate_limit.take();
+        }
+        self.filters.sent(quote.quote());
+        self.send_sequenced(udp_socket, quote.clone())
+    }
+
+    fn send_sequenced(
+        &mut self,
+        udp_socket: &UdpSocket,
+        quote: SharedQuote,
+    ) -> Result<(), SessionClosed> {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.send_datagram(udp_socket, sequence, quote.bytes())?;
+
+        if self.sent.len() == RETRANSMIT_CAPACITY {
+            self.sent.pop_front();
+        }
+        self.sent.push_back((sequence, quote));
+        Ok(())
+    }
+
+    /// Resends the buffered datagrams of the range, or a snapshot when some of them are gone.
+    fn retransmit(
+        &mut self,
+        context: &StreamQuotesContext,
+        from_seq: u64,
+        to_seq: u64,
+    ) -> Result<(), SessionClosed> {
+        let to_seq = to_seq.min(self.next_sequence - 1);
+        if from_seq > to_seq {
+            return Ok(());
+        }
+
+        let oldest = self
+            .sent
+            .front()
+            .map_or(self.next_sequence, |(sequence, _)| *sequence);
+        if from_seq < oldest {
+            info!(
+                "Datagrams {}..={} for {} are no longer buffered, sending snapshot",
+                from_seq, to_seq, self.address
+            );
+            let snapshot = match context.tickers_router.client_snapshot(&self.address) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    warn!("Failed to get snapshot for {}: {}", self.address, e);
+                    return Ok(());
+                }
+            };
+            for quote in snapshot {
+                self.send_sequenced(&context.udp_socket, quote)?;
+            }
+            return Ok(());
+        }
+
+        trace!(
+            "Retransmitting datagrams {}..={} to {}",
+            from_seq, to_seq, self.address
+        );
+        let resent: Vec<(u64, SharedQuote)> = self
+            .sent
+            .iter()
+            .filter(|(sequence, _)| (from_seq..=to_seq).contains(sequence))
+            .cloned()
+            .collect();
+        for (sequence, quote) in resent {
+            self.send_datagram(&context.udp_socket, sequence, quote.bytes())?;
+        }
+        Ok(())
+    }
+
+    fn send_datagram(
+        &mut self,
+        udp_socket: &UdpSocket,
+        sequence: u64,
+        response: &[u8],
+    ) -> Result<(), SessionClosed> {
+        let result = match (self.tcp.as_mut(), self.destination.as_ref()) {
+            (Some(tcp), _) => tcp.send(&[&Datagram::header(sequence), response]),
+            (None, Some(destination)) => {
+                send_datagram(udp_socket, sequence, response, destination.address().into())
+            }
+            (None, None) => Ok(()),
+        };
+        result.map_err(|e| {
+            warn!("Failed to send quote to {}: {}", self.address, e);
+            SessionClosed
+        })
+    }
+
+    fn is_throttled(&mut self, now: Instant) -> bool {
+        self.rate_limit.is_some()
+            && self.available(now) == 0
+            && (!self.quote_rx.is_empty()
+                || self
+                    .conflation
+                    .as_ref()
+                    .is_some_and(|conflation| conflation.has_due(now)))
+    }
+
+    fn notify_throttled(
+        &mut self,
+        udp_socket: &UdpSocket,
+        now: Instant,
+    ) -> Result<(), SessionClosed> {
+        let Some(rate_limit) = self.rate_limit.as_mut() else {
+            return Ok(());
+        };
+        if !rate_limit.should_notify(now) {
+            return Ok(());
+        }
+
+        trace!("Throttling quotes for {}", self.address);
+        let notice: Result<Vec<u8>, _> = Response::Throttled {
+            max_rate: rate_limit.rate(),
+        }
+        .try_into();
+        match notice {
+            Ok(notice) => self.send_datagram(udp_socket, Datagram::UNSEQUENCED, &notice),
+            Err(e) => {
+                warn!("Failed to serialize throttling notice: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        if self.last_ping_time.elapsed() > TIMEOUT {
+            info!(
+                "Last ping for {} was more than 5 seconds ago, stop streaming quotes",
+                self.address
+            );
+            return true;
+        }
+        false
+    }
+}
+
+struct SessionClosed;
+
+pub(crate) struct StreamQuotesPool {
+    workers: Vec<Sender<Session>>,
+}
+
+impl StreamQuotesPool {
+    pub(crate) fn add_session(&self, session: Session) -> Result<(), StreamQuotesPoolError> {
+        let mut hasher = DefaultHasher::new();
+        session.address.hash(&mut hasher);
+        let worker = hasher.finish() as usize % self.workers.len();
+        self.workers[worker]
+            .send(session)
+            .map_err(|_| StreamQuotesPoolError::WorkerStopped(worker))
+    }
+}
+
+#[instrument(name = "Run stream quotes workers", skip(context))]
+pub(crate) fn run_stream_quotes(
+    workers: usize,
+    context: StreamQuotesContext,
+) -> (StreamQuotesPool, Vec<JoinHandle<()>>) {
+    let context = Arc::new(context);
+    let mut senders = Vec::with_capacity(workers);
+    let mut threads = Vec::with_capacity(workers);
+    for worker in 0..workers {
+        let (session_tx, session_rx) = crossbeam_channel::unbounded::<Session>();
+        let context = Arc::clone(&context);
+        senders.push(session_tx);
+        threads.push(thread::spawn(move || {
+            stream_quotes(worker, context, session_rx)
+        }));
+    }
+    (StreamQuotesPool { workers: senders }, threads)
+}
+
+#[instrument(name = "Stream quotes", skip(context, session_rx))]
+fn stream_quotes(worker: usize, context: Arc<StreamQuotesContext>, session_rx: Receiver<Session>) {
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut last_expiration_check = Instant::now();
+
+    while !context.cancellation_token.is_cancelled() {
+        let now = Instant::now();
+        let ready: Vec<bool> = sessions
+            .iter_mut()
+            .map(|session| session.available(now) > 0)
+            .collect();
+
+        let mut select = Select::new();
+        select.recv(&session_rx);
+        for (session, ready) in sessions.iter().zip(ready) {
+            select.recv(&session.monitoring_rx);
+            if ready {
+                select.recv(&session.quote_rx);
+            }
+        }
+        let _ = select.ready_timeout(POLL_INTERVAL);
+
+        loop {
+            match session_rx.try_recv() {
+                Ok(session) => {
+                    info!("Start streaming quotes to {}", session.address);
+                    sessions.push(session);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+
+        let check_expiration = last_expiration_check.elapsed() >= POLL_INTERVAL;
+        if check_expiration {
+            last_expiration_check = Instant::now();
+        }
+
+        let mut closed = Vec::new();
+        for (i, session) in sessions.iter_mut().enumerate() {
+            let result = session
+                .receive_events(&context)
+                .and_then(|_| session.send_quotes(&context.udp_socket));
+            if result.is_err() || (check_expiration && session.is_expired()) {
+                closed.push(i);
+            }
+        }
+
+        for i in closed.into_iter().rev() {
+            let session = sessions.swap_remove(i);
+            close_session(&context, session);
+        }
+    }
+}
+
+fn close_session(context: &StreamQuotesContext, session: Session) {
+    let address = session.address;
+    if let Err(e) = context
+        .monitoring_router
+        .delete(&address, &session.monitoring_tx)
+    {
+        warn!("Failed to delete monitoring route for {}: {}", address, e);
+    }
+    // Pings of a bound ping source session are routed by their source as well
+    if let Some(destination) = session
+        .destination
+        .filter(|destination| *destination != address)
+        && let Err(e) = context
+            .monitoring_router
+            .delete(&destination, &session.monitoring_tx)
+    {
+        warn!(
+            "Failed to delete monitoring route for {}: {}",
+            destination, e
+        );
+    }
+    if let Err(e) = context
+        .tickers_router
+        .delete_clients(vec![(address.clone(), session.quote_rx)])
+    {
+        warn!("Failed to delete ticker routes for {}: {}", address, e);
+    }
+    info!("Stopped streaming quotes to {}", address);
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum StreamQuotesPoolError {
+    #[error("Stream quotes worker {0} has stopped")]
+    WorkerStopped(usize),
 }
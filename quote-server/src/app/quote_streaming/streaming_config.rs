@@ -0,0 +1,44 @@
+use crate::app::quote_streaming::OverflowPolicy;
+
+#[derive(Debug, Clone)]
+pub(crate) struct StreamingConfig {
+    workers: usize,
+    queue_capacity: usize,
+    overflow_policy: OverflowPolicy,
+    max_rate: Option<u32>,
+}
+
+impl StreamingConfig {
+    pub(crate) fn new(
+        workers: usize,
+        queue_capacity: usize,
+        overflow_policy: OverflowPolicy,
+        max_rate: Option<u32>,
+    ) -> Self {
+        Self {
+            workers,
+            queue_capacity,
+            overflow_policy,
+            max_rate,
+        }
+    }
+
+    pub(crate) fn workers(&self) -> usize {
+        self.workers
+    }
+
+    pub(crate) fn queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+
+    pub(crate) fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    pub(crate) fn rate_limit(&self, requested: Option<u32>) -> Option<u32> {
+        match (requested, self.max_rate) {
+            (Some(requested), Some(max_rate)) => Some(requested.min(max_rate)),
+            (requested, max_rate) => requested.or(max_rate),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use quote_streaming::{frame_header, split_frame};
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+
+/// Pending output above which the session stops taking quotes until the client catches up.
+const MAX_PENDING_OUTPUT: usize = 256 * 1024;
+const READ_BUFFER_SIZE: usize = 4096;
+
+/// A non-blocking TCP connection exchanging length-prefixed frames with a streaming client.
+pub(super) struct TcpTransport {
+    stream: TcpStream,
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+impl TcpTransport {
+    pub(super) fn new(stream: TcpStream) -> std::io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            input: Vec::new(),
+            output: Vec::new(),
+        })
+    }
+
+    /// Queues the parts as the payload of one frame and writes as much pending output as the
+    /// socket accepts.
+    pub(super) fn send(&mut self, parts: &[&[u8]]) -> std::io::Result<()> {
+        let len = parts.iter().map(|part| part.len()).sum();
+        self.output.extend_from_slice(&frame_header(len));
+        for part in parts {
+            self.output.extend_from_slice(part);
+        }
+        self.flush()
+    }
+
+    pub(super) fn flush(&mut self) -> std::io::Result<()> {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.output.drain(..written);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` while the client reads slower than quotes are written.
+    pub(super) fn is_congested(&self) -> bool {
+        self.output.len() >= MAX_PENDING_OUTPUT
+    }
+
+    /// Reads the available bytes and returns the payloads of the complete frames.
+    pub(super) fn receive(&mut self) -> std::io::Result<Vec<Vec<u8>>> {
+        let mut buffer = [0; READ_BUFFER_SIZE];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(len) => self.input.extend_from_slice(&buffer[..len]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut frames = Vec::new();
+        let mut consumed = 0;
+        while let Some((payload, len)) = split_frame(&self.input[consumed..])? {
+            frames.push(payload.to_vec());
+            consumed += len;
+        }
+        self.input.drain(..consumed);
+        Ok(frames)
+    }
+}
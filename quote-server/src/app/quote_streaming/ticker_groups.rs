@@ -0,0 +1,67 @@
+use quote_streaming::TickerGroup;
+use std::io::{BufRead, BufReader, Error, ErrorKind};
+use std::path::Path;
+
+/// Reads ticker groups, one `name: TICKER, TICKER, ...` definition per line.
+///
+/// Empty lines and lines starting with `#` are skipped.
+pub fn read_ticker_groups(path: &Path) -> Result<Vec<TickerGroup>, Error> {
+    let file = std::fs::File::open(path)?;
+    let buffer = BufReader::new(file);
+    let mut groups: Vec<TickerGroup> = Vec::new();
+    for line in buffer.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let group = parse_group(line).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        if groups.iter().any(|g| g.name() == group.name()) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("duplicate group `{}`", group.name()),
+            ));
+        }
+        groups.push(group);
+    }
+    Ok(groups)
+}
+
+fn parse_group(line: &str) -> Result<TickerGroup, String> {
+    let (name, tickers) = line
+        .split_once(':')
+        .ok_or_else(|| format!("expected `name: TICKER, ...`, got `{}`", line))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("group name is missing in `{}`", line));
+    }
+
+    let tickers: Vec<String> = tickers
+        .split(',')
+        .map(str::trim)
+        .filter(|ticker| !ticker.is_empty())
+        .map(String::from)
+        .collect();
+    if tickers.is_empty() {
+        return Err(format!("group `{}` has no tickers", name));
+    }
+
+    Ok(TickerGroup::new(name, tickers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_group() {
+        assert_eq!(
+            parse_group("tech: AAPL, MSFT,NV*").unwrap(),
+            TickerGroup::new("tech", vec!["AAPL".into(), "MSFT".into(), "NV*".into()])
+        );
+        assert!(parse_group("tech AAPL").is_err());
+        assert!(parse_group(": AAPL").is_err());
+        assert!(parse_group("banks: ").is_err());
+    }
+}
@@ -0,0 +1,68 @@
+/// Glob pattern over tickers: `*` matches any run of characters, `?` matches one character.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TickerPattern {
+    pattern: Vec<char>,
+}
+
+impl TickerPattern {
+    /// Returns a pattern if the subscription entry contains wildcards, `None` for plain tickers.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        value.contains(['*', '?']).then(|| Self {
+            pattern: value.chars().collect(),
+        })
+    }
+
+    pub(crate) fn matches(&self, ticker: &str) -> bool {
+        let ticker: Vec<char> = ticker.chars().collect();
+        let (mut p, mut t) = (0, 0);
+        let mut backtrack = None;
+
+        while t < ticker.len() {
+            match self.pattern.get(p) {
+                Some('*') => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                }
+                Some(&c) if c == '?' || c == ticker[t] => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => match backtrack {
+                    Some((star, matched)) => {
+                        backtrack = Some((star, matched + 1));
+                        p = star + 1;
+                        t = matched + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        self.pattern[p..].iter().all(|&c| c == '*')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(TickerPattern::parse("AAPL").is_none());
+
+        let all = TickerPattern::parse("*").unwrap();
+        assert!(all.matches("AAPL"));
+        assert!(all.matches(""));
+
+        let prefix = TickerPattern::parse("NV*").unwrap();
+        assert!(prefix.matches("NVDA"));
+        assert!(prefix.matches("NV"));
+        assert!(!prefix.matches("ANVX"));
+
+        let infix = TickerPattern::parse("A?P*L").unwrap();
+        assert!(infix.matches("AAPL"));
+        assert!(infix.matches("AMPXXL"));
+        assert!(!infix.matches("APL"));
+        assert!(!infix.matches("AAPLX"));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::app::quote_streaming::{MulticastGroups, TickersRouter};
+use crate::app::server_cancellation_token::ServerCancellationToken;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{info, instrument, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reads tickers, one per line, skipping empty lines.
+pub fn read_tickers(path: &Path) -> Result<Vec<String>, std::io::Error> {
+    let file = std::fs::File::open(path)?;
+    let buffer = BufReader::new(file);
+    let mut tickers = Vec::new();
+    for line in buffer.lines() {
+        let line = line?;
+        let ticker = line.trim();
+        if !ticker.is_empty() {
+            tickers.push(ticker.to_string());
+        }
+    }
+    Ok(tickers)
+}
+
+/// Watches the tickers file and reloads the ticker universe when it changes.
+#[instrument(name = "Run tickers reloader", skip_all)]
+pub(crate) fn run_tickers_reloader(
+    tickers_file: PathBuf,
+    tickers_router: Arc<TickersRouter>,
+    multicast_groups: Option<Arc<MulticastGroups>>,
+    cancellation_token: Arc<ServerCancellationToken>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        watch_tickers(
+            tickers_file,
+            tickers_router,
+            multicast_groups,
+            cancellation_token,
+        )
+    })
+}
+
+#[instrument(name = "Watch tickers file", skip_all, fields(file = %tickers_file.display()))]
+fn watch_tickers(
+    tickers_file: PathBuf,
+    tickers_router: Arc<TickersRouter>,
+    multicast_groups: Option<Arc<MulticastGroups>>,
+    cancellation_token: Arc<ServerCancellationToken>,
+) {
+    let mut modified = modified_time(&tickers_file);
+    let mut last_check = Instant::now();
+
+    while !cancellation_token.is_cancelled() {
+        thread::sleep(POLL_INTERVAL);
+        if last_check.elapsed() < RELOAD_CHECK_INTERVAL {
+            continue;
+        }
+        last_check = Instant::now();
+
+        let current = modified_time(&tickers_file);
+        if current.is_none() || current == modified {
+            continue;
+        }
+        modified = current;
+
+        let tickers = match read_tickers(&tickers_file) {
+            Ok(tickers) => tickers,
+            Err(e) => {
+                warn!("Failed to reload tickers: {}", e);
+                continue;
+            }
+        };
+        match tickers_router.set_tickers(tickers) {
+            Ok(added) => {
+                info!("Tickers reloaded, added: {:?}", added);
+                if let Some(groups) = multicast_groups.as_ref() {
+                    groups.add_tickers(&added);
+                }
+            }
+            Err(e) => warn!("Failed to update tickers: {}", e),
+        }
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -1,30 +1,230 @@
 use crate::app::client_address::ClientAddress;
-use crossbeam_channel::Sender;
-use quote_streaming::StockQuote;
+use crate::app::quote_streaming::{QuoteSendError, QuoteSender, SharedQuote, TickerPattern};
+use crossbeam_channel::{Receiver, Sender};
+use quote_streaming::{StockQuote, TickerGroup};
 use std::collections::{HashMap, HashSet};
 use std::sync::RwLock;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 
 pub(crate) struct TickersRouter {
-    tickers_router: RwLock<HashMap<String, HashMap<ClientAddress, Sender<StockQuote>>>>,
+    tickers_router: RwLock<HashMap<String, HashMap<ClientAddress, QuoteSender>>>,
     client_tickers: RwLock<HashMap<ClientAddress, Vec<String>>>,
+    /// The queue of every client with a session, kept to route tickers the client adds later.
+    client_senders: RwLock<HashMap<ClientAddress, QuoteSender>>,
+    pattern_routes: RwLock<HashMap<ClientAddress, PatternRoute>>,
+    universe: RwLock<Vec<String>>,
+    last_quotes: RwLock<HashMap<String, SharedQuote>>,
+    /// Services taking every quote, such as the multicast publisher. They are kept apart from
+    /// the client routes, so no client can take their place and no overflow policy drops them.
+    feeds: RwLock<Vec<Sender<SharedQuote>>>,
+    groups: Vec<TickerGroup>,
+}
+
+/// Patterns of a client, kept to route tickers that appear after a reload.
+struct PatternRoute {
+    patterns: Vec<TickerPattern>,
+    tx: QuoteSender,
 }
 
 impl TickersRouter {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(tickers: Vec<String>, groups: Vec<TickerGroup>) -> Self {
         Self {
             tickers_router: RwLock::new(HashMap::new()),
             client_tickers: RwLock::new(HashMap::new()),
+            client_senders: RwLock::new(HashMap::new()),
+            pattern_routes: RwLock::new(HashMap::new()),
+            universe: RwLock::new(tickers),
+            last_quotes: RwLock::new(HashMap::new()),
+            feeds: RwLock::new(Vec::new()),
+            groups,
         }
     }
 
+    pub(crate) fn groups(&self) -> &[TickerGroup] {
+        &self.groups
+    }
+
+    /// Returns an unbounded channel of every quote, including those of tickers added later.
+    pub(crate) fn add_feed(&self) -> Result<Receiver<SharedQuote>, TickersRouterError> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        self.feeds
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?
+            .push(tx);
+        Ok(rx)
+    }
+
+    /// Appends the tickers of the named groups to the subscription tickers.
+    pub(crate) fn expand_groups(
+        &self,
+        mut tickers: Vec<String>,
+        groups: &[String],
+    ) -> Result<Vec<String>, TickersRouterError> {
+        for name in groups {
+            let group = self
+                .groups
+                .iter()
+                .find(|group| group.name() == name)
+                .ok_or_else(|| TickersRouterError::UnknownGroup(name.clone()))?;
+            tickers.extend_from_slice(group.tickers());
+        }
+        Ok(tickers)
+    }
+
+    /// Tickers the server currently generates quotes for.
+    pub(crate) fn tickers(&self) -> Result<Vec<String>, TickersRouterError> {
+        let lock = self
+            .universe
+            .read()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        Ok(lock.clone())
+    }
+
+    /// Expands patterns against the current tickers, plain tickers are kept as is.
+    pub(crate) fn resolve(&self, tickers: &[String]) -> Result<Vec<String>, TickersRouterError> {
+        let universe = self
+            .universe
+            .read()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        Ok(resolve_tickers(tickers, &universe).0)
+    }
+
+    /// Latest known quotes of the tickers, patterns are expanded against the current tickers.
+    pub(crate) fn last_quotes(
+        &self,
+        tickers: &[String],
+    ) -> Result<Vec<StockQuote>, TickersRouterError> {
+        let tickers = self.resolve(tickers)?;
+        let last_quotes = self
+            .last_quotes
+            .read()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        Ok(tickers
+            .iter()
+            .filter_map(|ticker| last_quotes.get(ticker))
+            .map(|quote| quote.quote().clone())
+            .collect())
+    }
+
+    /// Latest known quotes of the tickers routed to the client, flagged as snapshots.
+    pub(crate) fn client_snapshot(
+        &self,
+        client_address: &ClientAddress,
+    ) -> Result<Vec<SharedQuote>, TickersRouterError> {
+        let client_lock = self
+            .client_tickers
+            .read()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let last_quotes = self
+            .last_quotes
+            .read()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+
+        let Some(tickers) = client_lock.get(client_address) else {
+            return Ok(Vec::new());
+        };
+        let mut snapshot = Vec::new();
+        for quote in tickers.iter().filter_map(|ticker| last_quotes.get(ticker)) {
+            snapshot.push(quote.snapshot()?);
+        }
+        Ok(snapshot)
+    }
+
+    /// Replaces the ticker universe, drops the routes of removed tickers and routes new
+    /// tickers to the clients whose patterns match them. Returns the added tickers.
+    #[instrument(name = "Set tickers", skip_all)]
+    pub(crate) fn set_tickers(
+        &self,
+        tickers: Vec<String>,
+    ) -> Result<Vec<String>, TickersRouterError> {
+        let mut route_lock = self
+            .tickers_router
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut client_lock = self
+            .client_tickers
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let pattern_lock = self
+            .pattern_routes
+            .read()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut universe = self
+            .universe
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut last_quotes = self
+            .last_quotes
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+
+        last_quotes.retain(|ticker, _| tickers.contains(ticker));
+        route_lock.retain(|ticker, _| tickers.contains(ticker));
+        client_lock.retain(|_, routed| {
+            routed.retain(|ticker| tickers.contains(ticker));
+            !routed.is_empty()
+        });
+        let added: Vec<String> = tickers
+            .iter()
+            .filter(|ticker| !universe.contains(ticker))
+            .cloned()
+            .collect();
+
+        for ticker in added.iter() {
+            for (client_address, route) in pattern_lock.iter() {
+                if route.patterns.iter().any(|pattern| pattern.matches(ticker)) {
+                    route_lock
+                        .entry(ticker.clone())
+                        .or_default()
+                        .insert(client_address.clone(), route.tx.clone());
+                    client_lock
+                        .entry(client_address.clone())
+                        .or_default()
+                        .push(ticker.clone());
+                    info!(
+                        "Added client to ticker route: client={}, ticker={}",
+                        client_address, ticker
+                    );
+                }
+            }
+        }
+
+        *universe = tickers;
+        Ok(added)
+    }
+
+    /// Routes the tickers to a new session of the client and queues the last known quote of
+    /// each ticker as a snapshot ahead of the live quotes. The routes of an earlier session
+    /// with the same address are replaced, which ends that session.
     #[instrument(name = "Add quote route", skip(self, tx), fields(address, tickers))]
     pub(crate) fn add_routes(
         &self,
         tickers: Vec<String>,
-        tx: Sender<StockQuote>,
+        tx: QuoteSender,
         client_address: ClientAddress,
     ) -> Result<(), TickersRouterError> {
+        self.route(&tickers, Some(tx), &client_address)?;
+        Ok(())
+    }
+
+    /// Routes more tickers to a client with a session, like [`TickersRouter::add_routes`].
+    /// Returns `false` when the client has no session.
+    pub(crate) fn add_client_tickers(
+        &self,
+        tickers: Vec<String>,
+        client_address: &ClientAddress,
+    ) -> Result<bool, TickersRouterError> {
+        self.route(&tickers, None, client_address)
+    }
+
+    /// Routes the tickers to the new session `tx`, or to the running session of the client
+    /// without one. Returns `false` when there is no session to route to.
+    fn route(
+        &self,
+        tickers: &[String],
+        tx: Option<QuoteSender>,
+        client_address: &ClientAddress,
+    ) -> Result<bool, TickersRouterError> {
         let mut route_lock = self
             .tickers_router
             .write()
@@ -33,8 +233,64 @@ impl TickersRouter {
             .client_tickers
             .write()
             .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut pattern_lock = self
+            .pattern_routes
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let universe = self
+            .universe
+            .read()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let last_quotes = self
+            .last_quotes
+            .read()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut sender_lock = self
+            .client_senders
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
 
-        for ticker in tickers {
+        let tx = match tx {
+            Some(tx) => {
+                if sender_lock.contains_key(client_address) {
+                    info!("Replacing the session of {}", client_address);
+                    remove_client(
+                        &mut route_lock,
+                        &mut client_lock,
+                        &mut pattern_lock,
+                        &mut sender_lock,
+                        client_address,
+                    );
+                }
+                sender_lock.insert(client_address.clone(), tx.clone());
+                tx
+            }
+            None => match sender_lock.get(client_address) {
+                Some(tx) => tx.clone(),
+                None => return Ok(false),
+            },
+        };
+
+        let (mut tickers, patterns) = resolve_tickers(tickers, &universe);
+        if !patterns.is_empty() {
+            let route = pattern_lock
+                .entry(client_address.clone())
+                .or_insert_with(|| PatternRoute {
+                    patterns: Vec::new(),
+                    tx: tx.clone(),
+                });
+            for pattern in patterns {
+                if !route.patterns.contains(&pattern) {
+                    route.patterns.push(pattern);
+                }
+            }
+        }
+        // Tickers added to a running session may be routed to it already
+        if let Some(routed) = client_lock.get(client_address) {
+            tickers.retain(|ticker| !routed.contains(ticker));
+        }
+
+        for ticker in tickers.iter() {
             route_lock
                 .entry(ticker.to_string())
                 .or_default()
@@ -44,23 +300,102 @@ impl TickersRouter {
                 .or_default()
                 .push(ticker.to_string());
         }
+
+        // Live quotes are routed under the read lock, so the snapshots cannot overtake them
+        for quote in tickers.iter().filter_map(|ticker| last_quotes.get(ticker)) {
+            if let Err(e) = tx.send(quote.snapshot()?) {
+                warn!("Failed to queue snapshot for {}: {}", client_address, e);
+                break;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Stops routing the tickers to the client. A pattern removes the routed tickers it
+    /// matches, and the same pattern of the subscription so reloaded tickers stay away too.
+    #[instrument(name = "Remove quote routes", skip(self), fields(address, tickers))]
+    pub(crate) fn remove_client_tickers(
+        &self,
+        tickers: &[String],
+        client_address: &ClientAddress,
+    ) -> Result<(), TickersRouterError> {
+        let mut route_lock = self
+            .tickers_router
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut client_lock = self
+            .client_tickers
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut pattern_lock = self
+            .pattern_routes
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+
+        let patterns: Vec<TickerPattern> = tickers
+            .iter()
+            .filter_map(|ticker| TickerPattern::parse(ticker))
+            .collect();
+        if let Some(route) = pattern_lock.get_mut(client_address) {
+            route.patterns.retain(|pattern| !patterns.contains(pattern));
+            if route.patterns.is_empty() {
+                pattern_lock.remove(client_address);
+            }
+        }
+
+        let Some(routed) = client_lock.get_mut(client_address) else {
+            return Ok(());
+        };
+        routed.retain(|ticker| {
+            let removed =
+                tickers.contains(ticker) || patterns.iter().any(|pattern| pattern.matches(ticker));
+            if removed && let Some(clients) = route_lock.get_mut(ticker) {
+                clients.remove(client_address);
+                info!(
+                    "Removed client from ticker route: client={}, ticker={}",
+                    client_address, ticker
+                );
+            }
+            !removed
+        });
         Ok(())
     }
 
     #[instrument(name = "Send quote", skip(self), fields(ticker = quote.ticker()))]
     pub(crate) fn send_quote(&self, quote: StockQuote) -> Result<(), TickersRouterError> {
-        let mut delete_client = HashSet::new();
+        let mut delete_client = Vec::new();
+        let quote = SharedQuote::new(quote)?;
 
         {
             let lock = self
                 .tickers_router
                 .read()
                 .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+            self.last_quotes
+                .write()
+                .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?
+                .insert(quote.ticker().to_string(), quote.clone());
 
+            // A feed only disconnects when its service stops with the server
+            for feed in self
+                .feeds
+                .read()
+                .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?
+                .iter()
+            {
+                let _ = feed.send(quote.clone());
+            }
             if let Some(clients) = lock.get(quote.ticker()) {
                 for (address, tx) in clients {
-                    if tx.send(quote.clone()).is_err() {
-                        delete_client.insert(address.clone());
+                    match tx.send(quote.clone()) {
+                        Ok(()) => {}
+                        Err(QuoteSendError::Overflow) => {
+                            warn!("Disconnecting slow consumer {}", address);
+                            delete_client.push((address.clone(), tx.queue().clone()));
+                        }
+                        Err(QuoteSendError::Disconnected) => {
+                            delete_client.push((address.clone(), tx.queue().clone()));
+                        }
                     }
                 }
             }
@@ -71,10 +406,12 @@ impl TickersRouter {
         Ok(())
     }
 
-    #[instrument(name = "Delete clients from quote route", skip(self), fields(clients = ?clients))]
+    /// Deletes the routes of the sessions reading from the given queues. The routes of a later
+    /// session with the same address are kept.
+    #[instrument(name = "Delete clients from quote route", skip_all)]
     pub(crate) fn delete_clients(
         &self,
-        clients: HashSet<ClientAddress>,
+        clients: Vec<(ClientAddress, Receiver<SharedQuote>)>,
     ) -> Result<(), TickersRouterError> {
         if clients.is_empty() {
             return Ok(());
@@ -88,25 +425,269 @@ impl TickersRouter {
             .client_tickers
             .write()
             .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut pattern_lock = self
+            .pattern_routes
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
+        let mut sender_lock = self
+            .client_senders
+            .write()
+            .map_err(|e| TickersRouterError::RwLockPoisoned(e.to_string()))?;
 
-        for client_address in clients.into_iter() {
-            if let Some(tickers) = client_lock.remove(&client_address) {
-                for ticker in tickers {
-                    route_lock.entry(ticker.clone()).and_modify(|clients| {
-                        clients.remove(&client_address);
-                        info!(
-                            "Removed client from ticker route: client={}, ticker={}",
-                            client_address, ticker
-                        );
-                    });
-                }
+        for (client_address, quote_rx) in clients.iter() {
+            if sender_lock
+                .get(client_address)
+                .is_some_and(|tx| tx.queue().same_channel(quote_rx))
+            {
+                remove_client(
+                    &mut route_lock,
+                    &mut client_lock,
+                    &mut pattern_lock,
+                    &mut sender_lock,
+                    client_address,
+                );
             }
         }
         Ok(())
     }
 }
+
+fn remove_client(
+    route_lock: &mut HashMap<String, HashMap<ClientAddress, QuoteSender>>,
+    client_lock: &mut HashMap<ClientAddress, Vec<String>>,
+    pattern_lock: &mut HashMap<ClientAddress, PatternRoute>,
+    sender_lock: &mut HashMap<ClientAddress, QuoteSender>,
+    client_address: &ClientAddress,
+) {
+    pattern_lock.remove(client_address);
+    sender_lock.remove(client_address);
+    if let Some(tickers) = client_lock.remove(client_address) {
+        for ticker in tickers {
+            route_lock.entry(ticker.clone()).and_modify(|clients| {
+                clients.remove(client_address);
+                info!(
+                    "Removed client from ticker route: client={}, ticker={}",
+                    client_address, ticker
+                );
+            });
+        }
+    }
+}
+
+fn resolve_tickers(tickers: &[String], universe: &[String]) -> (Vec<String>, Vec<TickerPattern>) {
+    let mut seen = HashSet::new();
+    let mut resolved = Vec::new();
+    let mut patterns = Vec::new();
+    for ticker in tickers {
+        match TickerPattern::parse(ticker) {
+            Some(pattern) => {
+                resolved.extend(
+                    universe
+                        .iter()
+                        .filter(|candidate| pattern.matches(candidate))
+                        .filter(|candidate| seen.insert(candidate.as_str()))
+                        .cloned(),
+                );
+                patterns.push(pattern);
+            }
+            None if seen.insert(ticker.as_str()) => resolved.push(ticker.clone()),
+            None => {}
+        }
+    }
+    (resolved, patterns)
+}
+
+/// Failures to route quotes to subscribers.
 #[derive(Debug, thiserror::Error)]
-pub(crate) enum TickersRouterError {
+pub enum TickersRouterError {
+    /// A thread panicked while holding the routes.
     #[error("Failed to get lock: {0}")]
     RwLockPoisoned(String),
+    /// A quote could not be serialized.
+    #[error("Failed to serialize quote: {0}")]
+    Serialization(#[from] rancor::Error),
+    /// The subscription names a ticker group the server does not define.
+    #[error("Unknown ticker group: {0}")]
+    UnknownGroup(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::quote_streaming::{OverflowPolicy, SessionQueue};
+    use quote_streaming::Response;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_patterns_follow_reloaded_tickers() {
+        let router = TickersRouter::new(
+            ["AAPL", "NVDA", "MSFT"].map(String::from).to_vec(),
+            Vec::new(),
+        );
+        let queue = SessionQueue::new(16, OverflowPolicy::DropOldest, Arc::default());
+        let (tx, rx) = queue.channel();
+        let client = ClientAddress::new("127.0.0.1".parse().unwrap(), 5153);
+
+        router
+            .add_routes(vec!["NV*".into(), "AAPL".into()], tx, client)
+            .unwrap();
+        let added = router
+            .set_tickers(["AAPL", "NVDA", "NVMI", "TSLA"].map(String::from).to_vec())
+            .unwrap();
+        assert_eq!(added, vec!["NVMI".to_string(), "TSLA".to_string()]);
+
+        for ticker in ["NVMI", "TSLA", "MSFT", "NVDA"] {
+            router
+                .send_quote(StockQuote::new(ticker, 100, 1, 0))
+                .unwrap();
+        }
+        let received: Vec<String> = rx.try_iter().map(|q| q.ticker().to_string()).collect();
+        assert_eq!(received, vec!["NVMI".to_string(), "NVDA".to_string()]);
+    }
+
+    #[test]
+    fn test_removed_tickers_are_unrouted() {
+        let router = TickersRouter::new(["AAPL", "MSFT"].map(String::from).to_vec(), Vec::new());
+        let queue = SessionQueue::new(16, OverflowPolicy::DropOldest, Arc::default());
+        let (tx, _rx) = queue.channel();
+        let (other_tx, _other_rx) = queue.channel();
+        let client = ClientAddress::new("127.0.0.1".parse().unwrap(), 5153);
+        let other = ClientAddress::new("127.0.0.1".parse().unwrap(), 5154);
+        router
+            .add_routes(vec!["AAPL".into(), "MSFT".into()], tx, client.clone())
+            .unwrap();
+        router
+            .add_routes(vec!["MSFT".into()], other_tx, other.clone())
+            .unwrap();
+
+        router.set_tickers(vec!["AAPL".to_string()]).unwrap();
+        let routes = router.tickers_router.read().unwrap();
+        assert_eq!(routes.keys().collect::<Vec<_>>(), vec!["AAPL"]);
+        let client_tickers = router.client_tickers.read().unwrap();
+        assert_eq!(client_tickers.get(&client), Some(&vec!["AAPL".to_string()]));
+        assert!(!client_tickers.contains_key(&other));
+    }
+
+    #[test]
+    fn test_change_client_tickers() {
+        let router = TickersRouter::new(
+            ["AAPL", "NVDA", "MSFT"].map(String::from).to_vec(),
+            Vec::new(),
+        );
+        let queue = SessionQueue::new(16, OverflowPolicy::DropOldest, Arc::default());
+        let (tx, rx) = queue.channel();
+        let client = ClientAddress::new("127.0.0.1".parse().unwrap(), 5153);
+        let stranger = ClientAddress::new("127.0.0.1".parse().unwrap(), 5154);
+
+        router
+            .add_routes(vec!["AAPL".into()], tx, client.clone())
+            .unwrap();
+        assert!(
+            router
+                .add_client_tickers(vec!["NV*".into(), "MSFT".into()], &client)
+                .unwrap()
+        );
+        assert!(
+            !router
+                .add_client_tickers(vec!["MSFT".into()], &stranger)
+                .unwrap()
+        );
+        router
+            .remove_client_tickers(&["NV*".into(), "AAPL".into()], &client)
+            .unwrap();
+        router
+            .set_tickers(["AAPL", "NVDA", "NVMI", "MSFT"].map(String::from).to_vec())
+            .unwrap();
+
+        for ticker in ["AAPL", "NVDA", "NVMI", "MSFT"] {
+            router
+                .send_quote(StockQuote::new(ticker, 100, 1, 0))
+                .unwrap();
+        }
+        let received: Vec<String> = rx.try_iter().map(|q| q.ticker().to_string()).collect();
+        assert_eq!(received, vec!["MSFT".to_string()]);
+    }
+
+    #[test]
+    fn test_new_session_replaces_routes() {
+        let router = TickersRouter::new(
+            ["AAPL", "NVDA", "MSFT"].map(String::from).to_vec(),
+            Vec::new(),
+        );
+        let queue = SessionQueue::new(16, OverflowPolicy::DropOldest, Arc::default());
+        let (old_tx, old_rx) = queue.channel();
+        let (new_tx, new_rx) = queue.channel();
+        let client = ClientAddress::new("127.0.0.1".parse().unwrap(), 5153);
+
+        router
+            .add_routes(vec!["NV*".into(), "AAPL".into()], old_tx, client.clone())
+            .unwrap();
+        router
+            .add_routes(vec!["MSFT".into()], new_tx, client.clone())
+            .unwrap();
+        // The old session closing late leaves the routes of the new one alone
+        router
+            .delete_clients(vec![(client.clone(), old_rx.clone())])
+            .unwrap();
+
+        for ticker in ["AAPL", "NVDA", "MSFT"] {
+            router
+                .send_quote(StockQuote::new(ticker, 100, 1, 0))
+                .unwrap();
+        }
+        let received: Vec<String> = new_rx.try_iter().map(|q| q.ticker().to_string()).collect();
+        assert_eq!(received, vec!["MSFT".to_string()]);
+        assert!(old_rx.try_recv().is_err_and(|e| e.is_disconnected()));
+    }
+
+    #[test]
+    fn test_feed_outlives_slow_consumers() {
+        let router = TickersRouter::new(vec!["AAPL".to_string()], Vec::new());
+        let feed = router.add_feed().unwrap();
+        let queue = SessionQueue::new(1, OverflowPolicy::Disconnect, Arc::default());
+        let (tx, _rx) = queue.channel();
+        let client = ClientAddress::new("127.0.0.1".parse().unwrap(), 5153);
+        router
+            .add_routes(vec!["*".into()], tx, client.clone())
+            .unwrap();
+
+        for price in 0..100 {
+            router
+                .send_quote(StockQuote::new("AAPL", price, 1, 0))
+                .unwrap();
+        }
+        // The client overflowed and was dropped, the feed got every quote
+        assert!(router.client_snapshot(&client).unwrap().is_empty());
+        assert_eq!(feed.try_iter().count(), 100);
+    }
+
+    #[test]
+    fn test_routes_start_with_snapshot() {
+        let router = TickersRouter::new(["AAPL", "MSFT"].map(String::from).to_vec(), Vec::new());
+        router
+            .send_quote(StockQuote::new("AAPL", 100, 1, 0))
+            .unwrap();
+        let queue = SessionQueue::new(16, OverflowPolicy::DropOldest, Arc::default());
+        let (tx, rx) = queue.channel();
+        let client = ClientAddress::new("127.0.0.1".parse().unwrap(), 5153);
+
+        router
+            .add_routes(vec!["AAPL".into(), "MSFT".into()], tx, client)
+            .unwrap();
+        router
+            .send_quote(StockQuote::new("AAPL", 101, 1, 1))
+            .unwrap();
+
+        let received: Vec<Response> = rx
+            .try_iter()
+            .map(|quote| Response::try_from(quote.bytes()).unwrap())
+            .collect();
+        assert_eq!(
+            received,
+            vec![
+                Response::SnapshotQuote(StockQuote::new("AAPL", 100, 1, 0)),
+                Response::Quote(StockQuote::new("AAPL", 101, 1, 1)),
+            ]
+        );
+    }
 }
@@ -0,0 +1,22 @@
+use quote_streaming::Datagram;
+use socket2::SockRef;
+use std::io::IoSlice;
+use std::net::{SocketAddr, UdpSocket};
+
+/// Sends a serialized response as a datagram with the given sequence number. The header and
+/// the shared response bytes go out in one vectored write, so fanning a quote out to many
+/// sessions copies it into no buffer of its own.
+pub(super) fn send_datagram(
+    udp_socket: &UdpSocket,
+    sequence: u64,
+    response: &[u8],
+    destination: SocketAddr,
+) -> std::io::Result<()> {
+    let header = Datagram::header(sequence);
+    SockRef::from(udp_socket)
+        .send_to_vectored(
+            &[IoSlice::new(&header), IoSlice::new(response)],
+            &destination.into(),
+        )
+        .map(|_| ())
+}
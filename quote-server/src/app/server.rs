@@ -0,0 +1,106 @@
+use crate::app::error::ServerError;
+use crate::app::server_cancellation_token::ServerCancellationToken;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::{info, trace};
+
+const STOP_DURATION: Duration = Duration::from_millis(100);
+const WAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A started server, streaming quotes until it is shut down.
+///
+/// Dropping the server shuts it down as well.
+pub struct Server {
+    local_addr: SocketAddr,
+    cancellation_token: Arc<ServerCancellationToken>,
+    service_threads: Vec<JoinHandle<()>>,
+}
+
+impl Server {
+    pub(crate) fn new(
+        local_addr: SocketAddr,
+        cancellation_token: Arc<ServerCancellationToken>,
+        service_threads: Vec<JoinHandle<()>>,
+    ) -> Self {
+        Self {
+            local_addr,
+            cancellation_token,
+            service_threads,
+        }
+    }
+
+    /// Returns the address clients connect to, with the port chosen by the system when the
+    /// server was built with port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns a handle that shuts the server down from another thread, e.g. a signal handler.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle(Arc::clone(&self.cancellation_token))
+    }
+
+    /// Blocks until the server is shut down through a [`ShutdownHandle`], then waits for its
+    /// threads to finish.
+    pub fn wait(mut self) -> Result<(), ServerError> {
+        while !self.cancellation_token.is_cancelled() {
+            thread::sleep(STOP_DURATION);
+        }
+        self.join_threads()
+    }
+
+    /// Stops accepting subscriptions, ends the sessions and waits for the threads to finish.
+    pub fn shutdown(mut self) -> Result<(), ServerError> {
+        self.cancellation_token.cancel();
+        self.join_threads()
+    }
+
+    fn join_threads(&mut self) -> Result<(), ServerError> {
+        self.wake_listener();
+        for (i, thread) in mem::take(&mut self.service_threads).into_iter().enumerate() {
+            trace!("Waiting for service thread {} to finish", i);
+            thread.join().map_err(|_| ServerError::ThreadJoin)?;
+        }
+
+        info!("Server stopped");
+        Ok(())
+    }
+
+    /// Connects to the listener blocked in `accept`, so it sees the cancellation and stops.
+    fn wake_listener(&self) {
+        let mut address = self.local_addr;
+        if address.ip().is_unspecified() {
+            address.set_ip(match address.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            });
+        }
+        // A listener that already stopped refuses the connection
+        if let Err(e) = TcpStream::connect_timeout(&address, WAKE_TIMEOUT) {
+            trace!("Failed to wake the listener: {}", e);
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        if !self.service_threads.is_empty() {
+            self.cancellation_token.cancel();
+            let _ = self.join_threads();
+        }
+    }
+}
+
+/// Shuts down a [`Server`].
+#[derive(Clone)]
+pub struct ShutdownHandle(Arc<ServerCancellationToken>);
+
+impl ShutdownHandle {
+    /// Signals the server to stop; [`Server::wait`] returns once it has.
+    pub fn shutdown(&self) {
+        self.0.cancel();
+    }
+}
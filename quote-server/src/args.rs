@@ -1,4 +1,5 @@
-use std::net::IpAddr;
+use quote_server::OverflowPolicy;
+use std::net::{IpAddr, Ipv4Addr};
 
 #[derive(Debug, clap::Parser)]
 pub(super) struct Args {
@@ -6,6 +7,10 @@ pub(super) struct Args {
     #[clap(short = 't', long)]
     pub tickers_file: std::path::PathBuf,
 
+    /// Path to the file with named ticker groups, one `name: TICKER, ...` per line
+    #[clap(short = 'g', long)]
+    pub groups_file: Option<std::path::PathBuf>,
+
     /// Port to listen on
     #[clap(short = 'p', long)]
     pub port: Option<u16>,
@@ -14,6 +19,54 @@ pub(super) struct Args {
     #[clap(short = 'a', long)]
     pub address: Option<IpAddr>,
 
+    /// Base multicast group address; enables multicast distribution
+    #[clap(long)]
+    pub multicast_address: Option<Ipv4Addr>,
+
+    /// Port the multicast groups are published to
+    #[clap(long, requires = "multicast_address")]
+    pub multicast_port: Option<u16>,
+
+    /// Number of multicast groups the tickers are spread over
+    #[clap(long, requires = "multicast_address")]
+    pub multicast_groups: Option<u8>,
+
+    /// Number of worker threads streaming quotes to the sessions
+    #[clap(short = 'w', long)]
+    pub workers: Option<usize>,
+
+    /// Number of threads reading and answering the requests
+    #[clap(long)]
+    pub connection_handlers: Option<usize>,
+
+    /// Maximum number of quotes queued for a session
+    #[clap(long)]
+    pub queue_capacity: Option<usize>,
+
+    /// What to do when a session queue is full
+    #[arg(long, value_enum)]
+    pub overflow_policy: Option<OverflowPolicyArg>,
+
+    /// Maximum number of quotes per second sent to a session
+    #[clap(long)]
+    pub max_rate: Option<u32>,
+
+    /// Directory of the quote journal; enables history queries
+    #[clap(long)]
+    pub journal_dir: Option<std::path::PathBuf>,
+
+    /// Size in bytes after which a new journal segment is started
+    #[clap(long, requires = "journal_dir")]
+    pub journal_segment_size: Option<u64>,
+
+    /// Total size in bytes of the journal segments to keep
+    #[clap(long, requires = "journal_dir")]
+    pub journal_max_size: Option<u64>,
+
+    /// Age in seconds after which journal segments are removed
+    #[clap(long, requires = "journal_dir")]
+    pub journal_max_age: Option<u64>,
+
     /// Log level
     #[arg(short = 'l', long, value_enum)]
     pub log_level: Option<LogLevel>,
@@ -23,6 +76,24 @@ impl Args {
     pub(super) fn log_level(&self) -> &str {
         self.log_level.as_ref().unwrap_or(&LogLevel::Info).as_str()
     }
+
+    pub(super) fn overflow_policy(&self) -> OverflowPolicy {
+        match self.overflow_policy {
+            None | Some(OverflowPolicyArg::DropOldest) => OverflowPolicy::DropOldest,
+            Some(OverflowPolicyArg::Conflate) => OverflowPolicy::Conflate,
+            Some(OverflowPolicyArg::Disconnect) => OverflowPolicy::Disconnect,
+        }
+    }
+}
+
+#[derive(Debug, clap::ValueEnum, Clone, Copy)]
+pub(super) enum OverflowPolicyArg {
+    /// Drops the oldest queued quote
+    DropOldest,
+    /// Replaces the queued quote of the same ticker, or drops the oldest one
+    Conflate,
+    /// Ends the session
+    Disconnect,
 }
 
 #[derive(Debug, clap::ValueEnum, Clone)]
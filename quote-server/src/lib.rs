@@ -0,0 +1,34 @@
+//! Quote server streaming generated stock quotes to subscribed clients.
+//!
+//! [`ServerBuilder`] configures the listening address, the tickers quotes are generated for,
+//! multicast, the journal and the per-session limits, and starts the server in the current
+//! process. Binding to port 0 lets tests run servers side by side:
+//!
+//! ```no_run
+//! use quote_server::ServerBuilder;
+//!
+//! let server = ServerBuilder::new(vec!["AAPL".to_string()])
+//!     .with_port(0)
+//!     .start()?;
+//! let address = server.local_addr();
+//! // ... subscribe to `address` ...
+//! server.shutdown()?;
+//! # Ok::<(), quote_server::ServerError>(())
+//! ```
+
+#![deny(unreachable_pub)]
+#![warn(missing_docs)]
+mod app;
+
+/// The internals driven by the benchmarks.
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub use app::FanOut;
+/// Optional services and limits of a server.
+pub use app::{JournalConfig, MulticastConfig, OverflowPolicy};
+/// Failures of the optional services.
+pub use app::{JournalError, MulticastError, TickersRouterError};
+/// Starting and stopping a server.
+pub use app::{Server, ServerBuilder, ServerError, ShutdownHandle};
+/// Reading the tickers and ticker groups from files.
+pub use app::{read_ticker_groups, read_tickers};
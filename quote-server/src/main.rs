@@ -1,40 +1,78 @@
 #![deny(unreachable_pub)]
 
-use crate::app::App;
 use crate::args::Args;
 use crate::tracing::initialize_tracing_subscribe;
+use ::tracing::error;
 use clap::Parser;
-use std::io::{BufRead, BufReader};
-use std::net::{IpAddr, Ipv4Addr};
+use quote_server::{
+    JournalConfig, MulticastConfig, ServerBuilder, read_ticker_groups, read_tickers,
+};
+use std::time::Duration;
 
-mod app;
 mod args;
 mod tracing;
 
-const DEFAULT_PORT: u16 = 5152;
+const DEFAULT_MULTICAST_PORT: u16 = 5154;
+const DEFAULT_MULTICAST_GROUPS: u8 = 1;
+const DEFAULT_JOURNAL_SEGMENT_SIZE: u64 = 4 * 1024 * 1024;
+const DEFAULT_JOURNAL_MAX_SIZE: u64 = 256 * 1024 * 1024;
+const DEFAULT_JOURNAL_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
     initialize_tracing_subscribe(args.log_level());
 
-    let port = args.port.unwrap_or(DEFAULT_PORT);
-    let address = args
-        .address
-        .unwrap_or(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
-    let tickers = read_tickers(&args)?;
+    let tickers = read_tickers(&args.tickers_file)?;
+    let mut builder = ServerBuilder::new(tickers).with_tickers_file(args.tickers_file.clone());
+    if let Some(address) = args.address {
+        builder = builder.with_address(address);
+    }
+    if let Some(port) = args.port {
+        builder = builder.with_port(port);
+    }
+    if let Some(groups_file) = args.groups_file.as_ref() {
+        builder = builder.with_groups(read_ticker_groups(groups_file)?);
+    }
+    if let Some(multicast_address) = args.multicast_address {
+        builder = builder.with_multicast(MulticastConfig::new(
+            multicast_address,
+            args.multicast_port.unwrap_or(DEFAULT_MULTICAST_PORT),
+            args.multicast_groups.unwrap_or(DEFAULT_MULTICAST_GROUPS),
+        ));
+    }
 
-    let app = App::new(address, port, tickers);
-    app.run();
+    if let Some(workers) = args.workers {
+        builder = builder.with_workers(workers);
+    }
+    if let Some(connection_handlers) = args.connection_handlers {
+        builder = builder.with_connection_handlers(connection_handlers);
+    }
+    if let Some(queue_capacity) = args.queue_capacity {
+        builder = builder.with_queue_capacity(queue_capacity);
+    }
+    builder = builder.with_overflow_policy(args.overflow_policy());
+    if let Some(max_rate) = args.max_rate {
+        builder = builder.with_max_rate(max_rate);
+    }
 
-    Ok(())
+    if let Some(journal_dir) = args.journal_dir {
+        builder = builder.with_journal(JournalConfig::new(
+            journal_dir,
+            args.journal_segment_size
+                .unwrap_or(DEFAULT_JOURNAL_SEGMENT_SIZE),
+            args.journal_max_size.unwrap_or(DEFAULT_JOURNAL_MAX_SIZE),
+            args.journal_max_age
+                .map_or(DEFAULT_JOURNAL_MAX_AGE, Duration::from_secs),
+        ));
+    }
+
+    run(builder).inspect_err(|err| error!("{}", err))
 }
 
-fn read_tickers(args: &Args) -> Result<Vec<String>, std::io::Error> {
-    let file = std::fs::File::open(&args.tickers_file)?;
-    let buffer = BufReader::new(file);
-    let mut tickers = Vec::new();
-    for line in buffer.lines() {
-        tickers.push(line?.trim().to_string());
-    }
-    Ok(tickers)
+fn run(builder: ServerBuilder) -> Result<(), Box<dyn std::error::Error>> {
+    let server = builder.start()?;
+    let shutdown = server.shutdown_handle();
+    ctrlc::set_handler(move || shutdown.shutdown())?;
+    server.wait()?;
+    Ok(())
 }
@@ -0,0 +1,89 @@
+use crate::Response;
+use crate::bytes::from_bytes;
+use rkyv::rancor::{self, Source};
+use rkyv::util::AlignedVec;
+
+const HEADER_SIZE: usize = size_of::<u64>();
+
+/// A UDP datagram sent by the server: a sequence number followed by a serialized [`Response`].
+///
+/// Quotes streamed to a unicast session are numbered from 1 without gaps, so the client can
+/// detect lost datagrams and request a retransmission. Other datagrams, such as pongs,
+/// throttling notices and multicast quotes, are [`Datagram::UNSEQUENCED`].
+#[derive(Debug, PartialEq)]
+pub struct Datagram {
+    sequence: u64,
+    response: Response,
+}
+
+impl Datagram {
+    /// The sequence number of datagrams outside the quote sequence.
+    pub const UNSEQUENCED: u64 = 0;
+
+    /// Prepends the sequence number to an already serialized response.
+    pub fn encode(sequence: u64, response: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + response.len());
+        bytes.extend_from_slice(&Self::header(sequence));
+        bytes.extend_from_slice(response);
+        bytes
+    }
+
+    /// Returns the bytes written before the serialized response, for senders that write the
+    /// two parts with one vectored write instead of copying them together.
+    pub fn header(sequence: u64) -> [u8; HEADER_SIZE] {
+        sequence.to_le_bytes()
+    }
+
+    /// Returns the sequence number of the datagram.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the response carried by the datagram.
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Consumes the datagram, returning the response.
+    pub fn into_response(self) -> Response {
+        self.response
+    }
+}
+
+impl TryFrom<&[u8]> for Datagram {
+    type Error = rancor::Error;
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let Some((header, payload)) = value.split_first_chunk::<HEADER_SIZE>() else {
+            return Err(rancor::Error::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "datagram is shorter than its header",
+            )));
+        };
+
+        // The payload follows the header, so it is copied to be aligned for access
+        let mut aligned = AlignedVec::<16>::with_capacity(payload.len());
+        aligned.extend_from_slice(payload);
+        Ok(Self {
+            sequence: u64::from_le_bytes(*header),
+            response: from_bytes(&aligned)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datagram_round_trip() {
+        let response: Vec<u8> = Response::Pong.try_into().unwrap();
+        let bytes = Datagram::encode(42, &response);
+
+        let datagram = Datagram::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(datagram.sequence(), 42);
+        assert_eq!(datagram.into_response(), Response::Pong);
+
+        assert_eq!(bytes[..8], Datagram::header(42));
+        assert!(Datagram::try_from(&bytes[..4]).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::{Error, ErrorKind, Read, Write};
+
+const HEADER_SIZE: usize = size_of::<u32>();
+
+/// The largest accepted frame payload.
+pub const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// Prepends the little-endian `u32` length to the payload.
+pub fn encode_frame(payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_SIZE + payload.len());
+    frame.extend_from_slice(&frame_header(payload.len()));
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Returns the length prefix of a frame with a payload of `len` bytes, for writers that send
+/// the payload in several parts.
+pub fn frame_header(len: usize) -> [u8; HEADER_SIZE] {
+    (len as u32).to_le_bytes()
+}
+
+/// Writes the payload as one length-prefixed frame.
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> std::io::Result<()> {
+    if payload.len() > MAX_FRAME_SIZE {
+        return Err(Error::new(ErrorKind::InvalidInput, "frame is too large"));
+    }
+    writer.write_all(&encode_frame(payload))
+}
+
+/// Reads the payload of the next length-prefixed frame.
+pub fn read_frame<R: Read>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+    read_frame_limited(reader, MAX_FRAME_SIZE)
+}
+
+/// Reads the payload of the next length-prefixed frame, refusing payloads over `max_size`
+/// bytes before reading them.
+pub fn read_frame_limited<R: Read>(reader: &mut R, max_size: usize) -> std::io::Result<Vec<u8>> {
+    let mut header = [0; HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+    let len = u32::from_le_bytes(header) as usize;
+    if len > max_size {
+        return Err(Error::new(ErrorKind::InvalidData, "frame is too large"));
+    }
+
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// Splits the first complete frame off a buffer of received bytes.
+///
+/// Returns the payload and the number of bytes the frame occupies, or `None` when the frame
+/// has not been received completely yet.
+pub fn split_frame(buffer: &[u8]) -> std::io::Result<Option<(&[u8], usize)>> {
+    let Some((header, rest)) = buffer.split_first_chunk::<HEADER_SIZE>() else {
+        return Ok(None);
+    };
+    let len = u32::from_le_bytes(*header) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(Error::new(ErrorKind::InvalidData, "frame is too large"));
+    }
+    Ok(rest.get(..len).map(|payload| (payload, HEADER_SIZE + len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, b"first").unwrap();
+        bytes.extend_from_slice(&encode_frame(b"second"));
+
+        let (payload, len) = split_frame(&bytes).unwrap().unwrap();
+        assert_eq!(payload, b"first");
+        assert!(split_frame(&bytes[len..bytes.len() - 1]).unwrap().is_none());
+
+        let mut reader = &bytes[len..];
+        assert_eq!(read_frame(&mut reader).unwrap(), b"second");
+    }
+}
@@ -6,13 +6,34 @@
 #![deny(unreachable_pub)]
 #![warn(missing_docs)]
 mod bytes;
+mod datagram;
+mod frame;
+mod multicast_group;
+mod quote_filter;
 mod request;
 mod response;
 mod stock_quote;
+mod subscription_options;
+mod ticker_group;
 
+/// Sequenced UDP datagram sent by the server.
+pub use datagram::Datagram;
+/// Length-prefixed framing of messages on a TCP stream.
+pub use frame::{
+    MAX_FRAME_SIZE, encode_frame, frame_header, read_frame, read_frame_limited, split_frame,
+    write_frame,
+};
+/// Multicast group description.
+pub use multicast_group::MulticastGroup;
+/// Server-side filter of a ticker's quotes.
+pub use quote_filter::QuoteFilter;
 /// Request types for the quote streaming service.
-pub use request::Request;
+pub use request::{Delivery, MAX_REQUEST_SIZE, Request, SubscriptionChange};
 /// Response types from the quote streaming service.
 pub use response::Response;
 /// Stock quote data structure.
 pub use stock_quote::StockQuote;
+/// Optional settings of a quote subscription.
+pub use subscription_options::SubscriptionOptions;
+/// Server-defined named group of tickers.
+pub use ticker_group::TickerGroup;
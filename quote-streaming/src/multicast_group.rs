@@ -0,0 +1,40 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// A multicast group the server publishes quotes to.
+#[derive(Debug, Clone, Eq, PartialEq, Archive, Deserialize, Serialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct MulticastGroup {
+    /// The multicast address of the group.
+    address: IpAddr,
+    /// The UDP port the quotes are published to.
+    port: u16,
+    /// The ticker symbols published to this group.
+    tickers: Vec<String>,
+}
+
+impl MulticastGroup {
+    /// Creates a new `MulticastGroup`.
+    pub fn new(address: IpAddr, port: u16, tickers: Vec<String>) -> Self {
+        Self {
+            address,
+            port,
+            tickers,
+        }
+    }
+
+    /// Returns the multicast address of the group.
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    /// Returns the UDP port the quotes are published to.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the ticker symbols published to this group.
+    pub fn tickers(&self) -> &[String] {
+        &self.tickers
+    }
+}
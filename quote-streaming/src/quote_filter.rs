@@ -0,0 +1,111 @@
+use crate::StockQuote;
+use rkyv::{Archive, Deserialize, Serialize};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+/// Conditions a quote of one ticker has to meet before the server sends it.
+///
+/// All configured conditions must hold. Prices are kept in cents and the
+/// percentage change in basis points, matching the precision of [`StockQuote`].
+#[derive(Archive, Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct QuoteFilter {
+    /// The ticker symbol the filter applies to.
+    ticker: String,
+    /// The minimum price in cents.
+    price_above: Option<i64>,
+    /// The maximum price in cents.
+    price_below: Option<i64>,
+    /// The minimum price change since the last sent quote, in basis points.
+    min_change_bp: Option<u32>,
+    /// The minimum traded volume.
+    min_volume: Option<u32>,
+}
+
+impl QuoteFilter {
+    /// Creates a filter for the given ticker that lets every quote through.
+    pub fn new(ticker: &str) -> Self {
+        Self {
+            ticker: ticker.to_string(),
+            price_above: None,
+            price_below: None,
+            min_change_bp: None,
+            min_volume: None,
+        }
+    }
+
+    /// Only sends quotes priced at or above `price`.
+    pub fn with_price_above(mut self, price: Decimal) -> Self {
+        self.price_above = to_cents(price);
+        self
+    }
+
+    /// Only sends quotes priced at or below `price`.
+    pub fn with_price_below(mut self, price: Decimal) -> Self {
+        self.price_below = to_cents(price);
+        self
+    }
+
+    /// Only sends quotes whose price moved by at least `percent` since the last sent quote.
+    pub fn with_min_change_percent(mut self, percent: Decimal) -> Self {
+        self.min_change_bp = (percent.abs() * Decimal::ONE_HUNDRED).round().to_u32();
+        self
+    }
+
+    /// Only sends quotes with at least `volume` shares traded.
+    pub fn with_min_volume(mut self, volume: u32) -> Self {
+        self.min_volume = Some(volume);
+        self
+    }
+
+    /// Returns the ticker symbol the filter applies to.
+    pub fn ticker(&self) -> &str {
+        &self.ticker
+    }
+
+    /// Checks the quote against the filter.
+    ///
+    /// `last_sent_price` is the price of the last quote of this ticker sent to the
+    /// client; the percentage change condition passes when there is none.
+    pub fn matches(&self, quote: &StockQuote, last_sent_price: Option<i64>) -> bool {
+        let price = quote.price();
+        self.price_above.is_none_or(|above| price >= above)
+            && self.price_below.is_none_or(|below| price <= below)
+            && self
+                .min_volume
+                .is_none_or(|volume| quote.volume() >= volume)
+            && match (self.min_change_bp, last_sent_price) {
+                (Some(min_change_bp), Some(last_price)) if last_price != 0 => {
+                    let change_bp =
+                        (price - last_price).abs() as i128 * 10_000 / (last_price.abs() as i128);
+                    change_bp >= min_change_bp as i128
+                }
+                _ => true,
+            }
+    }
+}
+
+fn to_cents(price: Decimal) -> Option<i64> {
+    (price * Decimal::ONE_HUNDRED).round().to_i64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_matches() {
+        let quote = StockQuote::new("AAPL", 15_000, 200, 0);
+        let filter = QuoteFilter::new("AAPL")
+            .with_price_above(Decimal::new(100, 0))
+            .with_price_below(Decimal::new(200, 0))
+            .with_min_change_percent(Decimal::new(5, 1))
+            .with_min_volume(100);
+
+        assert!(filter.matches(&quote, None));
+        assert!(filter.matches(&quote, Some(14_900)));
+        assert!(!filter.matches(&quote, Some(14_950)));
+        assert!(!filter.matches(&StockQuote::new("AAPL", 25_000, 200, 0), None));
+        assert!(!filter.matches(&StockQuote::new("AAPL", 15_000, 50, 0), None));
+    }
+}
@@ -1,7 +1,11 @@
+use crate::SubscriptionOptions;
 use crate::bytes::{from_bytes, to_bytes};
 use rkyv::{Archive, Deserialize, Serialize, rancor};
 use std::net::IpAddr;
 
+/// The largest serialized request the server reads from a TCP connection.
+pub const MAX_REQUEST_SIZE: usize = 64 * 1024;
+
 /// Represents a request from a client to the quote streaming server.
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq)]
 #[rkyv(compare(PartialEq), derive(Debug))]
@@ -10,13 +14,125 @@ pub enum Request {
     StreamTickers {
         /// The list of ticker symbols to stream.
         ticker: Vec<String>,
+        /// Names of server-defined ticker groups to stream in addition to `ticker`.
+        groups: Vec<String>,
+        /// How the server should deliver the quotes to the client.
+        delivery: Delivery,
+        /// Optional settings of the subscription.
+        options: SubscriptionOptions,
+    },
+    /// A simple ping request to check server availability.
+    Ping,
+    /// A UDP ping of a client subscribed with [`Delivery::PingSource`].
+    ///
+    /// The first ping carrying the session's token binds the session to the source address of
+    /// the datagram; the server streams the quotes there from then on.
+    SessionPing {
+        /// The token the server answered the subscription with.
+        token: u64,
+    },
+    /// A request for the ticker groups defined by the server.
+    ///
+    /// The server answers with [`Response::Groups`](crate::Response::Groups).
+    ListGroups,
+    /// A request for the latest known quote of each ticker, answered on the same connection.
+    ///
+    /// The server answers with [`Response::Quotes`](crate::Response::Quotes).
+    Snapshot {
+        /// The ticker symbols or patterns to look up.
+        tickers: Vec<String>,
+    },
+    /// A request for the journaled quotes of a ticker, answered on the same connection.
+    ///
+    /// The server answers with [`Response::Quotes`](crate::Response::Quotes) holding the latest
+    /// `limit` quotes between `from` and `to` inclusive, oldest first. A `to` in the future
+    /// is taken as now, and a range that then spans more than a day (86,400 seconds) is
+    /// refused with [`Response::Error`](crate::Response::Error), however long the server
+    /// keeps its journal.
+    History {
+        /// The ticker symbol to look up.
+        ticker: String,
+        /// The Unix timestamp of the oldest quote to return.
+        from: i64,
+        /// The Unix timestamp of the newest quote to return.
+        to: i64,
+        /// The maximum number of quotes to return.
+        limit: u32,
+    },
+    /// A request to resend the datagrams of a unicast session that the client did not receive.
+    ///
+    /// Accepted over TCP from the host the session streams to, and over UDP from the
+    /// session's own address, where `address` and `port` are ignored. Datagrams that are no
+    /// longer buffered are replaced by a snapshot of the latest quotes of the session's
+    /// tickers.
+    Retransmit {
+        /// The IP address the session streams to.
+        address: IpAddr,
+        /// The port the session streams to.
+        port: u16,
+        /// The sequence number of the first missing datagram.
+        from_seq: u64,
+        /// The sequence number of the last missing datagram.
+        to_seq: u64,
+    },
+    /// A request to change what a running session streams, without subscribing again.
+    ///
+    /// Accepted over TCP, answered with [`Response::Ok`](crate::Response::Ok) once the session
+    /// is found, and over UDP like [`Request::Retransmit`]. A session streaming over TCP also
+    /// takes it as a frame on its connection, where `address` and `port` are ignored.
+    ChangeSubscription {
+        /// The IP address the session streams to.
+        address: IpAddr,
+        /// The port the session streams to.
+        port: u16,
+        /// What to change.
+        change: SubscriptionChange,
+    },
+}
+
+/// A change to the quotes a running session streams.
+#[derive(Archive, Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub enum SubscriptionChange {
+    /// Streams these tickers or glob patterns as well, starting with a snapshot of each.
+    Add(Vec<String>),
+    /// Stops streaming these tickers, or the tickers matching these glob patterns.
+    Remove(Vec<String>),
+    /// Stops sending quotes while keeping the session alive.
+    Pause,
+    /// Sends quotes again, starting with a snapshot of every streamed ticker.
+    Resume,
+}
+
+/// Describes how the server delivers streamed quotes to a client.
+#[derive(Archive, Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub enum Delivery {
+    /// Quotes are sent to the client via unicast UDP.
+    Unicast {
         /// The IP address of the client to send the quotes to.
         address: IpAddr,
         /// The port number of the client to send the quotes to.
         port: u16,
     },
-    /// A simple ping request to check server availability.
-    Ping,
+    /// Quotes are published once to the server's multicast groups.
+    ///
+    /// The server answers with [`Response::MulticastGroups`](crate::Response::MulticastGroups)
+    /// listing the groups the client has to join.
+    Multicast,
+    /// Quotes are streamed back over the TCP connection that carried the request.
+    ///
+    /// Every message the server writes to the connection afterwards, starting with the answer
+    /// to the request, is a length-prefixed frame holding a [`Datagram`](crate::Datagram).
+    /// The client keeps the session alive by writing framed [`Request::Ping`]s, each answered
+    /// with a framed [`Response::Pong`](crate::Response::Pong).
+    Tcp,
+    /// Quotes are sent via unicast UDP to the source address of the client's pings.
+    ///
+    /// Suited to clients behind NAT, which cannot know the address the server sees. The server
+    /// answers with [`Response::SessionToken`](crate::Response::SessionToken) and starts
+    /// streaming after the first [`Request::SessionPing`] with that token.
+    PingSource,
 }
 
 impl TryFrom<Request> for Vec<u8> {
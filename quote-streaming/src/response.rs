@@ -1,5 +1,5 @@
-use crate::StockQuote;
 use crate::bytes::{from_bytes, to_bytes};
+use crate::{MulticastGroup, StockQuote, TickerGroup};
 use rkyv::{Archive, Deserialize, Serialize, rancor};
 
 /// Represents a response from the quote streaming server to a client.
@@ -8,12 +8,30 @@ use rkyv::{Archive, Deserialize, Serialize, rancor};
 pub enum Response {
     /// A single stock quote update.
     Quote(StockQuote),
+    /// The last known quote of a ticker, sent when a subscription starts and before live updates.
+    SnapshotQuote(StockQuote),
     /// A response to a ping request.
     Pong,
     /// An error message indicating something went wrong.
     Error(String),
     /// A generic successful response.
     Ok,
+    /// A successful multicast subscription with the groups the client has to join.
+    MulticastGroups(Vec<MulticastGroup>),
+    /// A notice that the server delays quotes to stay within the rate limit of the subscription.
+    Throttled {
+        /// The enforced maximum number of quotes per second.
+        max_rate: u32,
+    },
+    /// The ticker groups defined by the server.
+    Groups(Vec<TickerGroup>),
+    /// The latest known quotes of the requested tickers; tickers without quotes are omitted.
+    Quotes(Vec<StockQuote>),
+    /// A successful subscription with [`Delivery::PingSource`](crate::Delivery::PingSource).
+    ///
+    /// The client has to send the token in its
+    /// [`Request::SessionPing`](crate::Request::SessionPing)s.
+    SessionToken(u64),
 }
 
 impl TryFrom<Response> for Vec<u8> {
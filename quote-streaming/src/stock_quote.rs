@@ -48,10 +48,37 @@ impl StockQuote {
         }
     }
 
+    /// Creates a `StockQuote` from its parts.
+    ///
+    /// `price` is the price in cents and `timestamp` the Unix timestamp in seconds.
+    pub fn new(ticker: &str, price: i64, volume: u32, timestamp: i64) -> Self {
+        Self {
+            ticker: ticker.to_string(),
+            price,
+            volume,
+            timestamp,
+        }
+    }
+
     /// Returns the ticker symbol of the stock.
     pub fn ticker(&self) -> &str {
         &self.ticker
     }
+
+    /// Returns the price of the stock in cents.
+    pub fn price(&self) -> i64 {
+        self.price
+    }
+
+    /// Returns the volume of shares traded in this update.
+    pub fn volume(&self) -> u32 {
+        self.volume
+    }
+
+    /// Returns the Unix timestamp when the quote was generated.
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
 }
 
 #[cfg(test)]
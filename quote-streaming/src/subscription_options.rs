@@ -0,0 +1,55 @@
+use crate::QuoteFilter;
+use rkyv::{Archive, Deserialize, Serialize};
+use std::time::Duration;
+
+/// Optional settings of a quote subscription.
+#[derive(Archive, Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct SubscriptionOptions {
+    /// The conflation interval in milliseconds, if the client wants conflated quotes.
+    conflation_interval_ms: Option<u64>,
+    /// The maximum number of quotes per second the client wants to receive.
+    max_rate: Option<u32>,
+    /// Filters the server applies to the quotes before sending them.
+    filters: Vec<QuoteFilter>,
+}
+
+impl SubscriptionOptions {
+    /// Requests at most one update per ticker per `interval`, carrying the latest quote.
+    ///
+    /// A zero interval disables conflation.
+    pub fn with_conflation(mut self, interval: Duration) -> Self {
+        self.conflation_interval_ms = Some(interval.as_millis() as u64).filter(|ms| *ms > 0);
+        self
+    }
+
+    /// Returns the conflation interval, if conflation was requested.
+    pub fn conflation(&self) -> Option<Duration> {
+        self.conflation_interval_ms.map(Duration::from_millis)
+    }
+
+    /// Limits the stream to `max_rate` quotes per second.
+    ///
+    /// The server may lower the limit according to its own policy.
+    /// A zero rate removes the limit.
+    pub fn with_max_rate(mut self, max_rate: u32) -> Self {
+        self.max_rate = Some(max_rate).filter(|rate| *rate > 0);
+        self
+    }
+
+    /// Returns the requested maximum number of quotes per second.
+    pub fn max_rate(&self) -> Option<u32> {
+        self.max_rate
+    }
+
+    /// Adds a filter for one ticker; quotes of that ticker are only sent when they match it.
+    pub fn with_filter(mut self, filter: QuoteFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Returns the per-ticker filters.
+    pub fn filters(&self) -> &[QuoteFilter] {
+        &self.filters
+    }
+}
@@ -0,0 +1,31 @@
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// A named group of tickers defined by the server, e.g. a sector or a watchlist.
+#[derive(Debug, Clone, Eq, PartialEq, Archive, Deserialize, Serialize)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub struct TickerGroup {
+    /// The name clients subscribe to the group by.
+    name: String,
+    /// The ticker symbols or patterns of the group.
+    tickers: Vec<String>,
+}
+
+impl TickerGroup {
+    /// Creates a new `TickerGroup`.
+    pub fn new(name: impl Into<String>, tickers: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            tickers,
+        }
+    }
+
+    /// Returns the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the ticker symbols or patterns of the group.
+    pub fn tickers(&self) -> &[String] {
+        &self.tickers
+    }
+}